@@ -0,0 +1,29 @@
+extern crate glfw_window;
+extern crate window;
+
+use glfw_window::GlfwWindow;
+use window::WindowSettings;
+
+fn main() {
+    let window = GlfwWindow::new(
+        &WindowSettings::new("GLFW Window", (640, 480))
+            .fullscreen(false)
+    ).unwrap();
+
+    // Include this output when reporting key mapping bugs.
+    println!("Platform: {:?}", window.platform());
+    println!("{:<16} {:<16} {:>8}  Name", "GLFW key", "Piston key", "Scancode");
+    for entry in window.keymap() {
+        let scancode = match entry.scancode {
+            Some(scancode) => scancode.to_string(),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<16} {:<16} {:>8}  {}",
+            format!("{:?}", entry.glfw_key),
+            format!("{:?}", entry.key),
+            scancode,
+            entry.name.unwrap_or_default()
+        );
+    }
+}
@@ -2,22 +2,62 @@
 
 //! A GLFW window back-end for the Piston game engine.
 
+#[cfg(feature = "vulkan")]
+extern crate ash;
+#[cfg(feature = "async")]
+extern crate futures_core;
 extern crate gl;
 extern crate glfw;
+#[cfg(feature = "image")]
+extern crate image;
 extern crate input;
+#[cfg(feature = "logging")]
+#[macro_use]
+extern crate log;
+#[cfg(feature = "raw-window-handle")]
+extern crate raw_window_handle;
 extern crate shader_version;
 extern crate window;
 
+// Without the `logging` feature, log messages are type checked but not logged.
+#[cfg(not(feature = "logging"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format!($($arg)*);
+        }
+    };
+}
+#[cfg(not(feature = "logging"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format!($($arg)*);
+        }
+    };
+}
+
 // External crates.
 use glfw::{Context, Joystick, JoystickId};
+use input::event_id::EventId;
 use input::{
     keyboard, Button, ButtonArgs, ButtonState, CloseArgs, ControllerAxisArgs, ControllerButton,
-    Event, FileDrag, Input, Motion, MouseButton, ResizeArgs,
+    Event, FileDrag, Input, Motion, MouseButton, ResizeArgs, TimeStamp,
 };
-use std::collections::VecDeque;
+use std::collections::{vec_deque, VecDeque};
 use std::error::Error;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::{fmt, ptr, slice};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{SystemTime, UNIX_EPOCH};
 use glfw::GlfwReceiver as Receiver;
 use window::{
     AdvancedWindow, Api, BuildFromWindowSettings, OpenGLWindow, Position, ProcAddress, Size,
@@ -26,6 +66,82 @@ use window::{
 
 pub use shader_version::OpenGL;
 
+/// The key binding used by `exit_on_esc`.
+const ESCAPE: Keybinding = Keybinding {
+    key: keyboard::Key::Escape,
+    mods: glfw::Modifiers::empty(),
+};
+
+/// Event id for screenshot events.
+///
+/// The event carries an `io::Result<PathBuf>` with the path of the saved screenshot.
+pub const SCREENSHOT: EventId = EventId("glfw_window/screenshot");
+
+/// Event id for wake events.
+///
+/// The event carries `()` and is queued by `Waker::wake`.
+pub const WAKE: EventId = EventId("glfw_window/wake");
+
+/// Identifies a window, for routing events when several windows are used.
+///
+/// Returned by `GlfwWindow::id`. Ids are unique within a process.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(pub u64);
+
+impl WindowId {
+    fn next() -> WindowId {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        WindowId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// The windowing platform GLFW is running on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Platform {
+    /// Microsoft Windows.
+    Win32,
+    /// macOS.
+    Cocoa,
+    /// The X Window System.
+    X11,
+    /// Wayland.
+    Wayland,
+    /// No windowing platform.
+    Null,
+}
+
+/// A native handle of a window, for platform features GLFW does not cover.
+///
+/// Returned by `GlfwWindow::native_handle`.
+/// The pointers are valid while the window exists.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NativeHandle {
+    /// A Windows `HWND`.
+    Win32 {
+        /// The window handle.
+        hwnd: *mut c_void,
+    },
+    /// A macOS `NSWindow`.
+    Cocoa {
+        /// The `NSWindow` object.
+        ns_window: *mut c_void,
+    },
+    /// An X11 window.
+    X11 {
+        /// The X11 `Window` id.
+        window: u64,
+        /// The X11 `Display` connection.
+        display: *mut c_void,
+    },
+    /// A Wayland surface.
+    Wayland {
+        /// The `wl_surface` of the window.
+        surface: *mut c_void,
+        /// The `wl_display` connection.
+        display: *mut c_void,
+    },
+}
+
 // list of joysticks to check
 const JOYSTICKS: [JoystickId; 16] = [
     JoystickId::Joystick1,
@@ -46,6 +162,940 @@ const JOYSTICKS: [JoystickId; 16] = [
     JoystickId::Joystick16,
 ];
 
+// list of keys known to GLFW
+const KEYS: [glfw::Key; 120] = [
+    glfw::Key::Space,
+    glfw::Key::Apostrophe,
+    glfw::Key::Comma,
+    glfw::Key::Minus,
+    glfw::Key::Period,
+    glfw::Key::Slash,
+    glfw::Key::Num0,
+    glfw::Key::Num1,
+    glfw::Key::Num2,
+    glfw::Key::Num3,
+    glfw::Key::Num4,
+    glfw::Key::Num5,
+    glfw::Key::Num6,
+    glfw::Key::Num7,
+    glfw::Key::Num8,
+    glfw::Key::Num9,
+    glfw::Key::Semicolon,
+    glfw::Key::Equal,
+    glfw::Key::A,
+    glfw::Key::B,
+    glfw::Key::C,
+    glfw::Key::D,
+    glfw::Key::E,
+    glfw::Key::F,
+    glfw::Key::G,
+    glfw::Key::H,
+    glfw::Key::I,
+    glfw::Key::J,
+    glfw::Key::K,
+    glfw::Key::L,
+    glfw::Key::M,
+    glfw::Key::N,
+    glfw::Key::O,
+    glfw::Key::P,
+    glfw::Key::Q,
+    glfw::Key::R,
+    glfw::Key::S,
+    glfw::Key::T,
+    glfw::Key::U,
+    glfw::Key::V,
+    glfw::Key::W,
+    glfw::Key::X,
+    glfw::Key::Y,
+    glfw::Key::Z,
+    glfw::Key::LeftBracket,
+    glfw::Key::Backslash,
+    glfw::Key::RightBracket,
+    glfw::Key::GraveAccent,
+    glfw::Key::World1,
+    glfw::Key::World2,
+    glfw::Key::Escape,
+    glfw::Key::Enter,
+    glfw::Key::Tab,
+    glfw::Key::Backspace,
+    glfw::Key::Insert,
+    glfw::Key::Delete,
+    glfw::Key::Right,
+    glfw::Key::Left,
+    glfw::Key::Down,
+    glfw::Key::Up,
+    glfw::Key::PageUp,
+    glfw::Key::PageDown,
+    glfw::Key::Home,
+    glfw::Key::End,
+    glfw::Key::CapsLock,
+    glfw::Key::ScrollLock,
+    glfw::Key::NumLock,
+    glfw::Key::PrintScreen,
+    glfw::Key::Pause,
+    glfw::Key::F1,
+    glfw::Key::F2,
+    glfw::Key::F3,
+    glfw::Key::F4,
+    glfw::Key::F5,
+    glfw::Key::F6,
+    glfw::Key::F7,
+    glfw::Key::F8,
+    glfw::Key::F9,
+    glfw::Key::F10,
+    glfw::Key::F11,
+    glfw::Key::F12,
+    glfw::Key::F13,
+    glfw::Key::F14,
+    glfw::Key::F15,
+    glfw::Key::F16,
+    glfw::Key::F17,
+    glfw::Key::F18,
+    glfw::Key::F19,
+    glfw::Key::F20,
+    glfw::Key::F21,
+    glfw::Key::F22,
+    glfw::Key::F23,
+    glfw::Key::F24,
+    glfw::Key::F25,
+    glfw::Key::Kp0,
+    glfw::Key::Kp1,
+    glfw::Key::Kp2,
+    glfw::Key::Kp3,
+    glfw::Key::Kp4,
+    glfw::Key::Kp5,
+    glfw::Key::Kp6,
+    glfw::Key::Kp7,
+    glfw::Key::Kp8,
+    glfw::Key::Kp9,
+    glfw::Key::KpDecimal,
+    glfw::Key::KpDivide,
+    glfw::Key::KpMultiply,
+    glfw::Key::KpSubtract,
+    glfw::Key::KpAdd,
+    glfw::Key::KpEnter,
+    glfw::Key::KpEqual,
+    glfw::Key::LeftShift,
+    glfw::Key::LeftControl,
+    glfw::Key::LeftAlt,
+    glfw::Key::LeftSuper,
+    glfw::Key::RightShift,
+    glfw::Key::RightControl,
+    glfw::Key::RightAlt,
+    glfw::Key::RightSuper,
+    glfw::Key::Menu,
+];
+
+/// Describes how a physical key maps on the current keyboard layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeymapEntry {
+    /// The GLFW key.
+    pub glfw_key: glfw::Key,
+    /// The Piston key on the current keyboard layout.
+    pub key: keyboard::Key,
+    /// The platform specific scancode, if the key exists on this keyboard.
+    pub scancode: Option<i32>,
+    /// The localized name of printable keys.
+    pub name: Option<String>,
+}
+
+/// Describes a connected controller.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ControllerInfo {
+    /// The id used in controller events.
+    pub id: u32,
+    /// The human-readable name, for example "Wireless Controller".
+    pub name: Option<String>,
+    /// The SDL compatible GUID, which identifies the model of controller.
+    pub guid: Option<String>,
+    /// The name from the gamepad mapping, if the controller is a known gamepad.
+    pub gamepad_name: Option<String>,
+}
+
+/// Plays rumble effects on controllers.
+///
+/// GLFW has no force feedback support, so this bridges to another library,
+/// such as SDL, that opens the same controller by its GUID.
+/// Used with `GlfwWindow::set_rumble_backend`.
+pub trait RumbleBackend {
+    /// Rumbles the controller with a GUID, returning `false` if it is not supported.
+    ///
+    /// The strengths of the low and high frequency motors are from 0 to 1.
+    fn rumble(&mut self, guid: &str, low: f32, high: f32, duration: Duration) -> bool;
+}
+
+/// A filter for input events, returning `false` to drop an event.
+///
+/// Used with `GlfwWindow::set_event_filter`.
+pub type EventFilter = Box<dyn FnMut(&mut Input) -> bool>;
+
+/// Remaps the axes and buttons of a controller before events are reported.
+///
+/// Used with `GlfwWindow::set_controller_remap`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ControllerRemap {
+    /// Maps axes to the axes they are reported as.
+    pub axes: HashMap<u8, u8>,
+    /// Axes that are inverted, by the axis they are reported as.
+    pub inverted_axes: Vec<u8>,
+    /// Maps buttons to the buttons they are reported as.
+    pub buttons: HashMap<u8, u8>,
+}
+
+impl ControllerRemap {
+    fn apply(&self, event: &mut Event) {
+        match *event {
+            Event::Input(Input::Move(Motion::ControllerAxis(ref mut args)), _) => {
+                if let Some(&axis) = self.axes.get(&args.axis) {
+                    args.axis = axis;
+                }
+                if self.inverted_axes.contains(&args.axis) {
+                    args.position = -args.position;
+                }
+            }
+            Event::Input(
+                Input::Button(ButtonArgs {
+                    button: Button::Controller(ref mut button),
+                    ..
+                }),
+                _,
+            ) => {
+                if let Some(&b) = self.buttons.get(&button.button) {
+                    button.button = b;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Controller events with the time they happened, for replaying them later.
+///
+/// Created by `GlfwWindow::stop_controller_recording`
+/// and replayed with `GlfwWindow::replay_controller_recording`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ControllerRecording {
+    /// The events, with the time since the recording started.
+    pub events: Vec<(Duration, Input)>,
+}
+
+impl ControllerRecording {
+    /// Saves the recording as text, one event per line.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut text = String::new();
+        for &(time, ref input) in &self.events {
+            let secs = time.as_secs_f64();
+            match *input {
+                Input::Move(Motion::ControllerAxis(args)) => text.push_str(&format!(
+                    "{} {} axis {} {}\n",
+                    secs, args.id, args.axis, args.position
+                )),
+                Input::Button(ButtonArgs {
+                    state,
+                    button: Button::Controller(button),
+                    ..
+                }) => text.push_str(&format!(
+                    "{} {} button {} {}\n",
+                    secs,
+                    button.id,
+                    button.button,
+                    (state == ButtonState::Press) as u8
+                )),
+                _ => {}
+            }
+        }
+        fs::write(path, text)
+    }
+
+    /// Loads a recording saved with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<ControllerRecording> {
+        let text = fs::read_to_string(path)?;
+        let mut events = vec![];
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, line.to_string());
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() != 5 {
+                return Err(invalid());
+            }
+            let time = parts[0].parse().map_err(|_| invalid())?;
+            let id = parts[1].parse().map_err(|_| invalid())?;
+            let index = parts[3].parse().map_err(|_| invalid())?;
+            let input = match parts[2] {
+                "axis" => Input::Move(Motion::ControllerAxis(ControllerAxisArgs::new(
+                    id,
+                    index,
+                    parts[4].parse().map_err(|_| invalid())?,
+                ))),
+                "button" => Input::Button(ButtonArgs {
+                    state: if parts[4] == "1" {
+                        ButtonState::Press
+                    } else {
+                        ButtonState::Release
+                    },
+                    button: Button::Controller(ControllerButton::new(id, index)),
+                    scancode: None,
+                }),
+                _ => return Err(invalid()),
+            };
+            let time = Duration::try_from_secs_f64(time).map_err(|_| invalid())?;
+            events.push((time, input));
+        }
+        Ok(ControllerRecording { events })
+    }
+}
+
+/// Statistics about events, collected when enabled with `GlfwWindow::set_event_metrics`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventMetrics {
+    /// The number of events in the last whole second, by kind of event.
+    ///
+    /// Custom events are counted by their event id.
+    pub events_per_second: HashMap<&'static str, u32>,
+    /// The largest number of events waiting in the event queue after a poll.
+    pub queue_high_water_mark: usize,
+    /// The time spent translating GLFW events in the last poll.
+    pub last_poll_duration: Duration,
+    /// The longest time spent translating GLFW events in a poll.
+    pub max_poll_duration: Duration,
+}
+
+/// Controls when `Motion::MouseRelative` events are generated from cursor movement.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MouseRelativeMode {
+    /// Always, from the difference between cursor positions.
+    Always,
+    /// Only while the cursor is captured.
+    WhileCaptured,
+    /// Only while the cursor is captured and raw mouse motion is supported,
+    /// so the deltas are free of pointer acceleration.
+    Raw,
+    /// Never.
+    Never,
+}
+
+/// Settings for normalizing scroll events.
+///
+/// Used with `GlfwWindow::set_scroll_settings`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScrollSettings {
+    /// Multiplies the scroll offsets reported by GLFW.
+    pub scale: f64,
+    /// Reports scrolling in whole steps only.
+    ///
+    /// Smooth scrolling from trackpads is accumulated until it adds up to a step.
+    pub discrete: bool,
+}
+
+impl Default for ScrollSettings {
+    fn default() -> ScrollSettings {
+        ScrollSettings {
+            scale: 1.0,
+            discrete: false,
+        }
+    }
+}
+
+/// Settings for moving a virtual cursor with a controller.
+///
+/// Used with `GlfwWindow::set_controller_cursor`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ControllerCursor {
+    /// The id of the controller that moves the cursor, as reported in its events.
+    pub id: u32,
+    /// The axis that moves the cursor horizontally.
+    pub x_axis: u8,
+    /// The axis that moves the cursor vertically.
+    pub y_axis: u8,
+    /// Cursor speed in pixels per second at full deflection.
+    pub speed: f64,
+    /// The controller button that acts as the left mouse button.
+    pub left_button: u8,
+    /// The controller button that acts as the right mouse button.
+    pub right_button: u8,
+}
+
+impl Default for ControllerCursor {
+    fn default() -> ControllerCursor {
+        ControllerCursor {
+            id: 0,
+            x_axis: 0,
+            y_axis: 1,
+            speed: 800.0,
+            left_button: 0,
+            right_button: 1,
+        }
+    }
+}
+
+/// Input to ignore while the window is unfocused or iconified.
+///
+/// Used with `GlfwWindow::set_pause_while_unfocused`.
+/// This keeps background applications from reacting to input
+/// and saves the cost of handling it.
+/// Focus, close, resize and other window events are still reported.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PauseWhileUnfocused {
+    /// Controllers are not polled.
+    pub controllers: bool,
+    /// GLFW stops reporting cursor movement, so no mouse motion events are emitted.
+    pub mouse_motion: bool,
+}
+
+/// Event id for characters typed with modifiers.
+///
+/// The event carries a `(char, glfw::Modifiers)` with the modifiers held down,
+/// useful for shortcuts like Ctrl+character that work across keyboard layouts.
+pub const CHAR_MODIFIERS: EventId = EventId("glfw_window/char_modifiers");
+
+/// Event id for window moves.
+///
+/// The event carries the new `Position` of the window.
+pub const WINDOW_MOVED: EventId = EventId("glfw_window/window_moved");
+
+/// Event id for window state changes.
+///
+/// The event carries a `WindowState`.
+pub const WINDOW_STATE: EventId = EventId("glfw_window/window_state");
+
+/// Event id for content scale changes.
+///
+/// The event carries the new `(f32, f32)` content scale of the window,
+/// for example after it moved to a monitor with another DPI.
+/// A resize event reports the new draw size.
+pub const CONTENT_SCALE: EventId = EventId("glfw_window/content_scale");
+
+/// Event id for grouped file drops.
+///
+/// The event carries a `FileDrop` with all files dropped at once.
+/// Used instead of `FileDrag::Drop` events when enabled with `set_group_file_drops`.
+pub const FILE_DROP: EventId = EventId("glfw_window/file_drop");
+
+/// Files dropped on a window together, reported with `FILE_DROP` events.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileDrop {
+    /// The paths of the dropped files.
+    pub paths: Vec<PathBuf>,
+    /// The cursor position the files were dropped at, in window coordinates.
+    pub position: [f64; 2],
+}
+
+/// The state of a window, reported with `WINDOW_STATE` events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WindowState {
+    /// The window was maximized.
+    Maximized,
+    /// The window was iconified (minimized).
+    Iconified,
+    /// The window was restored from being maximized or iconified.
+    Restored,
+}
+
+/// A standard cursor shape.
+///
+/// GLFW 3.3 does not provide diagonal resize or not-allowed cursors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CursorShape {
+    /// The regular arrow.
+    Arrow,
+    /// The text input I-beam.
+    IBeam,
+    /// The crosshair.
+    Crosshair,
+    /// The pointing hand.
+    Hand,
+    /// The horizontal resize arrow.
+    HResize,
+    /// The vertical resize arrow.
+    VResize,
+}
+
+impl From<CursorShape> for glfw::StandardCursor {
+    fn from(shape: CursorShape) -> glfw::StandardCursor {
+        use glfw::StandardCursor;
+
+        match shape {
+            CursorShape::Arrow => StandardCursor::Arrow,
+            CursorShape::IBeam => StandardCursor::IBeam,
+            CursorShape::Crosshair => StandardCursor::Crosshair,
+            CursorShape::Hand => StandardCursor::Hand,
+            CursorShape::HResize => StandardCursor::HResize,
+            CursorShape::VResize => StandardCursor::VResize,
+        }
+    }
+}
+
+/// The state a window is created in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InitialState {
+    /// The window is shown normally.
+    Normal,
+    /// The window is maximized.
+    Maximized,
+    /// The window is iconified (minimized).
+    Iconified,
+    /// The window is hidden until `AdvancedWindow::show` is called.
+    Hidden,
+}
+
+/// Window settings specific to the GLFW back-end.
+///
+/// Used with `GlfwWindow::new_with_glfw_settings`.
+#[derive(Clone, Debug)]
+pub struct GlfwSettings {
+    /// The target refresh rate when fullscreen, see `GlfwWindow::set_refresh_rate`.
+    pub refresh_rate: Option<u32>,
+    /// The state the window is created in.
+    pub initial_state: InitialState,
+    /// Whether the window stays on top of other windows.
+    pub always_on_top: bool,
+}
+
+impl Default for GlfwSettings {
+    fn default() -> GlfwSettings {
+        GlfwSettings {
+            refresh_rate: None,
+            initial_state: InitialState::Normal,
+            always_on_top: false,
+        }
+    }
+}
+
+/// Selects a monitor.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MonitorId {
+    /// The primary monitor.
+    Primary,
+    /// The monitor at an index in the list of connected monitors.
+    Index(usize),
+    /// The monitor with a name.
+    Name(String),
+}
+
+/// The physical size of a monitor and the size of its current video mode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MonitorSize {
+    /// The width in millimetres.
+    pub width_mm: u32,
+    /// The height in millimetres.
+    pub height_mm: u32,
+    /// The width in screen coordinates of the current video mode.
+    pub width: u32,
+    /// The height in screen coordinates of the current video mode.
+    pub height: u32,
+}
+
+impl MonitorSize {
+    /// Returns the horizontal and vertical screen coordinates per inch.
+    ///
+    /// Where screen coordinates are not pixels, such as on macOS Retina displays,
+    /// multiply by the monitor content scale to get pixels per inch.
+    /// Returns `None` if the physical size is not known.
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        if self.width_mm == 0 || self.height_mm == 0 {
+            return None;
+        }
+        Some((
+            self.width as f64 * 25.4 / self.width_mm as f64,
+            self.height as f64 * 25.4 / self.height_mm as f64,
+        ))
+    }
+}
+
+/// Describes a video mode of a monitor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+    /// The width in screen coordinates.
+    pub width: u32,
+    /// The height in screen coordinates.
+    pub height: u32,
+    /// The refresh rate in Hz.
+    pub refresh_rate: u32,
+}
+
+impl From<glfw::VidMode> for VideoMode {
+    fn from(mode: glfw::VidMode) -> VideoMode {
+        VideoMode {
+            width: mode.width,
+            height: mode.height,
+            refresh_rate: mode.refresh_rate,
+        }
+    }
+}
+
+/// Optional features available on the current platform.
+///
+/// Returned by `GlfwWindow::capabilities`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    /// Whether the window position can be read and set.
+    pub window_position: bool,
+    /// Whether the framebuffer of the window is transparent.
+    pub transparency: bool,
+    /// Whether raw mouse motion is supported.
+    pub raw_mouse_motion: bool,
+    /// Whether monitor gamma can be set.
+    pub gamma: bool,
+    /// Whether the clipboard can be used.
+    pub clipboard: bool,
+    /// Whether the window opacity can be set.
+    pub opacity: bool,
+    /// Whether the window can be kept on top of other windows.
+    pub always_on_top: bool,
+}
+
+/// Proof that the code runs on the main thread.
+///
+/// GLFW must be initialized, create windows and pump events on the main thread
+/// on every platform, and fails on other threads on macOS.
+/// Functions that must run on the main thread take this marker,
+/// which can not be sent to other threads.
+/// To handle events on another thread, see `GlfwWindow::forward_events`.
+#[derive(Copy, Clone, Debug)]
+pub struct MainThread {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl MainThread {
+    /// Returns the marker when called on the main thread.
+    ///
+    /// The thread is checked with the operating system on macOS and Linux.
+    /// Windows has no notion of a main thread, so there the marker is only returned
+    /// on the thread that first created a window with this crate, or on any thread before that.
+    /// On other platforms the thread is not checked.
+    pub fn new() -> Option<MainThread> {
+        if is_main_thread() {
+            Some(MainThread {
+                _not_send: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Creates the marker without checking the current thread.
+    ///
+    /// # Safety
+    ///
+    /// Must be called on the main thread of the process.
+    pub unsafe fn new_unchecked() -> MainThread {
+        MainThread {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+/// Returns `true` when called on the main thread of the process.
+#[cfg(target_os = "macos")]
+fn is_main_thread() -> bool {
+    extern "C" {
+        fn pthread_main_np() -> std::os::raw::c_int;
+    }
+    unsafe { pthread_main_np() != 0 }
+}
+
+/// Returns `true` when called on the main thread of the process.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn is_main_thread() -> bool {
+    extern "C" {
+        fn getpid() -> std::os::raw::c_int;
+        fn gettid() -> std::os::raw::c_int;
+    }
+    // The main thread is the one whose thread id is the process id.
+    unsafe { gettid() == getpid() }
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetCurrentThreadId() -> u32;
+}
+
+/// The thread that initialized GLFW in `GlfwWindow::create`, or 0 before that.
+#[cfg(windows)]
+static GLFW_THREAD: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+/// Returns `true` when called on the thread that initialized GLFW,
+/// or on any thread before GLFW is initialized.
+#[cfg(windows)]
+fn is_main_thread() -> bool {
+    match GLFW_THREAD.load(Ordering::SeqCst) {
+        0 => true,
+        thread => thread == unsafe { GetCurrentThreadId() },
+    }
+}
+
+/// Remembers the current thread as the one that initialized GLFW.
+#[cfg(windows)]
+fn remember_glfw_thread() {
+    let thread = unsafe { GetCurrentThreadId() };
+    let _ = GLFW_THREAD.compare_exchange(0, thread, Ordering::SeqCst, Ordering::SeqCst);
+}
+
+#[cfg(not(windows))]
+fn remember_glfw_thread() {}
+
+/// Returns `true`, since there is no way to find the main thread on this platform.
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "android",
+    windows
+)))]
+fn is_main_thread() -> bool {
+    true
+}
+
+/// An error for creating a window outside the main thread.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NotMainThreadError;
+
+impl fmt::Display for NotMainThreadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "GLFW windows must be created on the main thread, \
+             use `GlfwWindow::forward_events` to handle events on another thread"
+        )
+    }
+}
+
+impl Error for NotMainThreadError {}
+
+/// An error reported by GLFW.
+///
+/// Retrieved with `GlfwWindow::glfw_errors`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlfwError {
+    /// The kind of error.
+    pub error: glfw::Error,
+    /// A description of the error.
+    pub description: String,
+}
+
+impl fmt::Display for GlfwError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.error, self.description)
+    }
+}
+
+impl Error for GlfwError {}
+
+/// Event id for configuration changes the back-end does not apply itself.
+///
+/// The event carries a `Vec<(String, String)>` of the changed keys and values.
+pub const CONFIG_CHANGED: EventId = EventId("glfw_window/config_changed");
+
+/// Event id for configuration values the back-end rejects.
+///
+/// The event carries a `Vec<(String, String)>` of the keys and values that are not valid,
+/// such as a `width` that is not a positive number.
+pub const CONFIG_INVALID: EventId = EventId("glfw_window/config_invalid");
+
+/// A key together with the modifiers that must be held down.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Keybinding {
+    /// The key.
+    pub key: keyboard::Key,
+    /// The modifiers that must be held down.
+    pub mods: glfw::Modifiers,
+}
+
+impl Keybinding {
+    /// Creates a new key binding.
+    pub fn new(key: keyboard::Key, mods: glfw::Modifiers) -> Keybinding {
+        Keybinding { key, mods }
+    }
+
+    /// Returns `true` if a key pressed with modifiers triggers the binding.
+    fn matches(&self, key: keyboard::Key, mods: glfw::Modifiers) -> bool {
+        self.key == key && mods.contains(self.mods)
+    }
+}
+
+/// Settings for saving screenshots with a hotkey.
+///
+/// Used with `GlfwWindow::set_screenshot_hotkey`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScreenshotHotkey {
+    /// The key that takes a screenshot.
+    pub key: keyboard::Key,
+    /// The directory to save screenshots in.
+    pub dir: PathBuf,
+}
+
+impl Default for ScreenshotHotkey {
+    fn default() -> ScreenshotHotkey {
+        ScreenshotHotkey {
+            key: keyboard::Key::F12,
+            dir: PathBuf::from("screenshots"),
+        }
+    }
+}
+
+/// RGBA pixels of a rendered frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameCapture {
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+    /// RGBA pixels, starting with the top row.
+    pub pixels: Vec<u8>,
+}
+
+impl FrameCapture {
+    /// Saves the frame as an uncompressed TGA image.
+    pub fn save_tga<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut data = Vec::with_capacity(18 + self.pixels.len());
+        data.extend_from_slice(&[0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[self.width as u8, (self.width >> 8) as u8]);
+        data.extend_from_slice(&[self.height as u8, (self.height >> 8) as u8]);
+        // 32 bits per pixel, 8 alpha bits, top-left origin.
+        data.extend_from_slice(&[32, 0x28]);
+        for rgba in self.pixels.chunks(4) {
+            data.extend_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]);
+        }
+        fs::File::create(path)?.write_all(&data)
+    }
+}
+
+/// Guides a user through mapping a controller to the standard gamepad layout.
+///
+/// Created by `GlfwWindow::gamepad_mapping_wizard`.
+/// Ask the user to press the input named by `current`, call `update` after polling events
+/// until it returns `true`, and repeat until `current` returns `None`.
+/// The result is an SDL_GameControllerDB compatible mapping string.
+pub struct GamepadMappingWizard {
+    joystick: Joystick,
+    step: usize,
+    // The state of the controller when no input is used.
+    rest_axes: Vec<f32>,
+    // Whether the controller must return to rest before the next input is recorded.
+    waiting_for_rest: bool,
+    recorded: Vec<(&'static str, String)>,
+}
+
+impl GamepadMappingWizard {
+    /// The inputs of the standard gamepad layout, in the order they are asked for.
+    pub const STEPS: [&'static str; 21] = [
+        "a",
+        "b",
+        "x",
+        "y",
+        "back",
+        "guide",
+        "start",
+        "leftstick",
+        "rightstick",
+        "leftshoulder",
+        "rightshoulder",
+        "dpup",
+        "dpdown",
+        "dpleft",
+        "dpright",
+        "leftx",
+        "lefty",
+        "rightx",
+        "righty",
+        "lefttrigger",
+        "righttrigger",
+    ];
+
+    fn new(joystick: Joystick) -> GamepadMappingWizard {
+        let rest_axes = joystick.get_axes();
+        GamepadMappingWizard {
+            joystick,
+            step: 0,
+            rest_axes,
+            waiting_for_rest: false,
+            recorded: vec![],
+        }
+    }
+
+    /// Returns the name of the input the user should press or move next.
+    pub fn current(&self) -> Option<&'static str> {
+        GamepadMappingWizard::STEPS.get(self.step).cloned()
+    }
+
+    /// Skips the current input, leaving it unmapped.
+    pub fn skip(&mut self) {
+        if self.step < GamepadMappingWizard::STEPS.len() {
+            self.step += 1;
+        }
+    }
+
+    /// Checks the controller and records the current input when it is used.
+    ///
+    /// Returns `true` when an input was recorded.
+    pub fn update(&mut self) -> bool {
+        let name = match self.current() {
+            Some(name) => name,
+            None => return false,
+        };
+        if !self.joystick.is_present() {
+            return false;
+        }
+
+        let buttons = self.joystick.get_buttons();
+        let axes = self.joystick.get_axes();
+        let hats = self.joystick.get_hats();
+        let mut used = None;
+        for (i, &b) in buttons.iter().enumerate() {
+            if b != 0 {
+                used = Some(format!("b{}", i));
+            }
+        }
+        for (i, &a) in axes.iter().enumerate() {
+            let rest = self.rest_axes.get(i).cloned().unwrap_or(0.0);
+            if (a - rest).abs() > 0.5 {
+                used = Some(format!("a{}", i));
+            }
+        }
+        for (i, hat) in hats.iter().enumerate() {
+            if !hat.is_empty() {
+                used = Some(format!("h{}.{}", i, hat.bits()));
+            }
+        }
+
+        if self.waiting_for_rest {
+            self.waiting_for_rest = used.is_some();
+            return false;
+        }
+        match used {
+            Some(input) => {
+                self.recorded.push((name, input));
+                self.step += 1;
+                self.waiting_for_rest = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the SDL_GameControllerDB compatible mapping for the recorded inputs.
+    pub fn mapping(&self) -> String {
+        let guid = self.joystick.get_guid().unwrap_or_default();
+        let name = self
+            .joystick
+            .get_name()
+            .unwrap_or_default()
+            .replace(',', " ");
+        let mut mapping = format!("{},{},", guid, name);
+        for &(element, ref input) in &self.recorded {
+            mapping.push_str(&format!("{}:{},", element, input));
+        }
+        let platform = if cfg!(target_os = "windows") {
+            "Windows"
+        } else if cfg!(target_os = "macos") {
+            "Mac OS X"
+        } else {
+            "Linux"
+        };
+        mapping.push_str(&format!("platform:{},", platform));
+        mapping
+    }
+}
+
 /// Contains stuff for game window.
 pub struct GlfwWindow {
     /// The window.
@@ -54,32 +1104,111 @@ pub struct GlfwWindow {
     events: Receiver<(f64, glfw::WindowEvent)>,
     /// GLFW context.
     pub glfw: glfw::Glfw,
-    event_queue: VecDeque<Input>,
+    event_queue: VecDeque<Event>,
     // Used to compute relative mouse movement.
     last_mouse_pos: Option<(f64, f64)>,
     // The back-end does not remember the title.
     title: String,
-    exit_on_esc: bool,
+    exit_shortcut: Option<Keybinding>,
+    toggle_fullscreen_on_alt_enter: bool,
     automatic_close: bool,
 
-    /// ignore controller axis inputs below this threshold
+    /// ignore controller axis inputs below this threshold, after reporting a return to 0
     pub joystick_deadzone: f64,
+    /// ignore controller axis changes smaller than this, except returns to rest
+    pub joystick_axis_epsilon: f64,
     joysticks: Vec<JoystickHelper>,
+    // Connections and disconnections reported by GLFW.
+    joystick_events: mpsc::Receiver<(JoystickId, bool)>,
+    glfw_errors: mpsc::Receiver<GlfwError>,
+    id: WindowId,
+    // Events sent from other threads.
+    injected_events: (mpsc::Sender<Input>, mpsc::Receiver<Input>),
+    // Set by wakers.
+    woken: Arc<AtomicBool>,
+    // The window and framebuffer sizes reported by GLFW.
+    last_resize: ResizeArgs,
+    gamepad_mapping: bool,
+    normalize_triggers: bool,
+    controller_remaps: HashMap<u32, ControllerRemap>,
+    rumble_backend: Option<Box<dyn RumbleBackend>>,
+    stable_controller_ids: Option<StableControllerIds>,
+    pause_while_unfocused: PauseWhileUnfocused,
+    // The recording and when it started.
+    controller_recording: Option<(Instant, ControllerRecording)>,
+    // The recording, when the replay started, and the next event to replay.
+    controller_replay: Option<(Instant, ControllerRecording, usize)>,
+    event_filter: Option<EventFilter>,
+    subscribers: Vec<mpsc::Sender<Event>>,
+    fps_in_title: Option<FpsCounter>,
+    metrics: Option<MetricsCollector>,
+    controller_cursor: Option<ControllerCursor>,
+    // Used to compute how far the controller cursor moves.
+    last_controller_cursor_update: Option<Instant>,
+    sticky_modifiers: Option<StickyModifiers>,
+    event_sender: Option<mpsc::Sender<Event>>,
+    screenshot_hotkey: Option<ScreenshotHotkey>,
+    screenshot_requested: bool,
+    frame_reader: Option<FrameReader>,
+    paste_as_text: bool,
+    // Whether the key release of a paste shortcut should be swallowed.
+    paste_key_down: bool,
+    config_watcher: Option<ConfigWatcher>,
+    event_indicator: Option<EventIndicator>,
+    cursor_confinement: Option<[f64; 4]>,
+    cursor_hidden: bool,
+    mouse_relative: MouseRelativeMode,
+    coalesce_mouse_moves: bool,
+    group_file_drops: bool,
+    drop_position: Option<[f64; 2]>,
+    key_repeat: bool,
+    text_input: bool,
+    mouse_motion: bool,
+    physical_keys: bool,
+    modifiers: glfw::Modifiers,
+    scroll_settings: ScrollSettings,
+    // Scrolling not yet reported in discrete mode.
+    scroll_remainder: [f64; 2],
+    // Whether the cursor confinement follows the window size.
+    confine_to_window: bool,
+    // The back-end can not query the swap interval.
+    swap_interval: Option<glfw::SwapInterval>,
+    // Windows created for Vulkan have no OpenGL context.
+    opengl: bool,
+    drag_regions: Vec<[f64; 4]>,
+    resize_border: Option<f64>,
+    window_drag: Option<WindowDrag>,
+    // Position and size to restore when leaving fullscreen.
+    windowed_rect: Option<(i32, i32, i32, i32)>,
+    refresh_rate: Option<u32>,
 }
 
 impl GlfwWindow {
     /// Create a new game window from an existing GLFW window.
+    ///
+    /// GLFW 3.3 can not query the title of a window, so `get_title` returns
+    /// a placeholder until the title is set. Use `from_pieces_with_title`
+    /// to keep the title accurate.
+    ///
+    /// The GLFW joystick callback is replaced to detect controller connections,
+    /// and the error callback to report errors with `glfw_errors`.
     pub fn from_pieces(
         mut win: glfw::PWindow,
-        glfw: glfw::Glfw,
+        mut glfw: glfw::Glfw,
         events: Receiver<(f64, glfw::WindowEvent)>,
         exit_on_esc: bool,
     ) -> GlfwWindow {
-        win.set_all_polling(true);
-        win.make_current();
+        set_polling(&mut win, true, true);
+        win.set_store_lock_key_mods(true);
+        let opengl = win.get_client_api() != glfw::ffi::NO_API;
+        if opengl {
+            win.make_current();
+        }
         let title = "<unknown window title, created from_pieces>";
 
         // setup joysticks
+        let joystick_events = watch_joysticks(&mut glfw);
+        let glfw_errors = watch_errors(&mut glfw);
         let mut joysticks = Vec::new();
         for &i in &JOYSTICKS {
             joysticks.push(JoystickHelper::new(glfw.get_joystick(i)));
@@ -87,46 +1216,159 @@ impl GlfwWindow {
 
         GlfwWindow {
             joysticks,
+            joystick_events,
+            glfw_errors,
+            id: WindowId::next(),
+            injected_events: mpsc::channel(),
+            woken: Arc::new(AtomicBool::new(false)),
+            last_resize: resize_args(&win),
             window: win,
             events,
             glfw,
-            exit_on_esc,
+            exit_shortcut: if exit_on_esc { Some(ESCAPE) } else { None },
+            toggle_fullscreen_on_alt_enter: false,
             event_queue: VecDeque::new(),
             last_mouse_pos: None,
             title: title.to_string(),
             automatic_close: true,
             joystick_deadzone: 0.0,
+            joystick_axis_epsilon: 0.0,
+            gamepad_mapping: false,
+            normalize_triggers: false,
+            controller_remaps: HashMap::new(),
+            rumble_backend: None,
+            stable_controller_ids: None,
+            pause_while_unfocused: PauseWhileUnfocused::default(),
+            controller_recording: None,
+            controller_replay: None,
+            event_filter: None,
+            subscribers: Vec::new(),
+            fps_in_title: None,
+            metrics: None,
+            controller_cursor: None,
+            last_controller_cursor_update: None,
+            sticky_modifiers: None,
+            event_sender: None,
+            screenshot_hotkey: None,
+            screenshot_requested: false,
+            frame_reader: None,
+            paste_as_text: false,
+            paste_key_down: false,
+            config_watcher: None,
+            event_indicator: None,
+            cursor_confinement: None,
+            cursor_hidden: false,
+            mouse_relative: MouseRelativeMode::Always,
+            coalesce_mouse_moves: false,
+            group_file_drops: false,
+            drop_position: None,
+            key_repeat: false,
+            text_input: true,
+            mouse_motion: true,
+            physical_keys: false,
+            modifiers: glfw::Modifiers::empty(),
+            scroll_settings: ScrollSettings::default(),
+            scroll_remainder: [0.0, 0.0],
+            confine_to_window: false,
+            swap_interval: None,
+            opengl,
+            drag_regions: Vec::new(),
+            resize_border: None,
+            window_drag: None,
+            windowed_rect: None,
+            refresh_rate: None,
         }
     }
 
+    /// Create a new game window from an existing GLFW window, setting its title.
+    pub fn from_pieces_with_title(
+        win: glfw::PWindow,
+        glfw: glfw::Glfw,
+        events: Receiver<(f64, glfw::WindowEvent)>,
+        exit_on_esc: bool,
+        title: String,
+    ) -> GlfwWindow {
+        let mut window = GlfwWindow::from_pieces(win, glfw, events, exit_on_esc);
+        window.set_title(title);
+        window
+    }
+
     /// Creates a new game window for GLFW.
+    ///
+    /// When the settings ask for the Vulkan graphics API, the window has no
+    /// OpenGL context, and rendering goes through a surface from `create_surface`.
+    ///
+    /// Returns `NotMainThreadError` when not called on the main thread.
     pub fn new(settings: &WindowSettings) -> Result<GlfwWindow, Box<dyn Error>> {
+        let main_thread = MainThread::new().ok_or(NotMainThreadError)?;
+        GlfwWindow::new_on_main_thread(settings, main_thread)
+    }
+
+    /// Creates a new game window for GLFW with settings specific to this back-end.
+    ///
+    /// Returns `NotMainThreadError` when not called on the main thread.
+    pub fn new_with_glfw_settings(
+        settings: &WindowSettings,
+        glfw_settings: &GlfwSettings,
+    ) -> Result<GlfwWindow, Box<dyn Error>> {
+        let main_thread = MainThread::new().ok_or(NotMainThreadError)?;
+        GlfwWindow::create(settings, glfw_settings, main_thread)
+    }
+
+    /// Creates a new game window for GLFW on the main thread.
+    pub fn new_on_main_thread(
+        settings: &WindowSettings,
+        main_thread: MainThread,
+    ) -> Result<GlfwWindow, Box<dyn Error>> {
+        GlfwWindow::create(settings, &GlfwSettings::default(), main_thread)
+    }
+
+    fn create(
+        settings: &WindowSettings,
+        glfw_settings: &GlfwSettings,
+        _: MainThread,
+    ) -> Result<GlfwWindow, Box<dyn Error>> {
         use glfw::SwapInterval;
 
         // Initialize GLFW.
         let mut glfw = glfw::init_no_callbacks()?;
+        remember_glfw_thread();
+        let glfw_errors = watch_errors(&mut glfw);
 
         let api = settings
             .get_maybe_graphics_api()
             .unwrap_or(Api::opengl(3, 2));
-        if api.api != "OpenGL" {
+        let opengl = api.api == "OpenGL";
+        if !opengl && api.api != "Vulkan" {
             return Err(UnsupportedGraphicsApiError {
                 found: api.api,
-                expected: vec!["OpenGL".into()],
+                expected: vec!["OpenGL".into(), "Vulkan".into()],
             }
             .into());
         };
 
-        // Make sure we have the right GL version.
-        glfw.window_hint(glfw::WindowHint::ContextVersion(api.major, api.minor));
+        if opengl {
+            // Make sure we have the right GL version.
+            glfw.window_hint(glfw::WindowHint::ContextVersion(api.major, api.minor));
+        } else {
+            // Vulkan renders to a surface created for the window instead of a context.
+            glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::NoApi));
+        }
         glfw.window_hint(glfw::WindowHint::Resizable(settings.get_resizable()));
         glfw.window_hint(glfw::WindowHint::Decorated(settings.get_decorated()));
+        glfw.window_hint(glfw::WindowHint::Floating(glfw_settings.always_on_top));
+        glfw.window_hint(glfw::WindowHint::Maximized(
+            glfw_settings.initial_state == InitialState::Maximized,
+        ));
+        glfw.window_hint(glfw::WindowHint::Visible(
+            glfw_settings.initial_state != InitialState::Hidden,
+        ));
         glfw.window_hint(glfw::WindowHint::TransparentFramebuffer(
             settings.get_transparent(),
         ));
         // Set sRGB.
         glfw.window_hint(glfw::WindowHint::SRgbCapable(settings.get_srgb()));
-        if api >= Api::opengl(3, 2) {
+        if opengl && api >= Api::opengl(3, 2) {
             if cfg!(target_os = "macos") {
                 glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
             }
@@ -134,7 +1376,7 @@ impl GlfwWindow {
                 glfw::OpenGlProfileHint::Core,
             ));
         }
-        if settings.get_samples() != 0 {
+        if opengl && settings.get_samples() != 0 {
             glfw.window_hint(glfw::WindowHint::Samples(Some(
                 settings.get_samples() as u32
             )));
@@ -148,20 +1390,35 @@ impl GlfwWindow {
                 &settings.get_title(),
                 glfw::WindowMode::Windowed,
             )
-            .ok_or("Failed to create GLFW window.")?;
-        window.set_all_polling(true);
-        window.make_current();
+            .ok_or_else(|| {
+                let mut message = "Failed to create GLFW window.".to_string();
+                for err in glfw_errors.try_iter() {
+                    message.push_str(&format!(" {}", err));
+                }
+                message
+            })?;
+        set_polling(&mut window, true, true);
+        window.set_store_lock_key_mods(true);
 
-        if settings.get_vsync() {
-            glfw.set_swap_interval(SwapInterval::Sync(1));
-        } else {
-            glfw.set_swap_interval(SwapInterval::None);
-        }
+        let swap_interval = if opengl {
+            window.make_current();
+
+            let swap_interval = if settings.get_vsync() {
+                SwapInterval::Sync(1)
+            } else {
+                SwapInterval::None
+            };
+            glfw.set_swap_interval(swap_interval);
 
-        // Load the OpenGL function pointers.
-        gl::load_with(|s| window.get_proc_address(s) as *const _);
+            // Load the OpenGL function pointers.
+            gl::load_with(|s| window.get_proc_address(s) as *const _);
+            Some(swap_interval)
+        } else {
+            None
+        };
 
         // setup joysticks
+        let joystick_events = watch_joysticks(&mut glfw);
         let mut joysticks = Vec::new();
         if settings.get_controllers() {
             for &i in &JOYSTICKS {
@@ -169,107 +1426,2268 @@ impl GlfwWindow {
             }
         }
 
-        Ok(GlfwWindow {
+        let mut window = GlfwWindow {
             joysticks,
+            joystick_events,
+            glfw_errors,
+            id: WindowId::next(),
+            injected_events: mpsc::channel(),
+            woken: Arc::new(AtomicBool::new(false)),
+            last_resize: resize_args(&window),
             window,
             events,
             glfw,
             event_queue: VecDeque::new(),
             last_mouse_pos: None,
             title: settings.get_title(),
-            exit_on_esc: settings.get_exit_on_esc(),
+            exit_shortcut: if settings.get_exit_on_esc() {
+                Some(ESCAPE)
+            } else {
+                None
+            },
+            toggle_fullscreen_on_alt_enter: false,
             automatic_close: settings.get_automatic_close(),
             joystick_deadzone: 0.0,
-        })
+            joystick_axis_epsilon: 0.0,
+            gamepad_mapping: false,
+            normalize_triggers: false,
+            controller_remaps: HashMap::new(),
+            rumble_backend: None,
+            stable_controller_ids: None,
+            pause_while_unfocused: PauseWhileUnfocused::default(),
+            controller_recording: None,
+            controller_replay: None,
+            event_filter: None,
+            subscribers: Vec::new(),
+            fps_in_title: None,
+            metrics: None,
+            controller_cursor: None,
+            last_controller_cursor_update: None,
+            sticky_modifiers: None,
+            event_sender: None,
+            screenshot_hotkey: None,
+            screenshot_requested: false,
+            frame_reader: None,
+            paste_as_text: false,
+            paste_key_down: false,
+            config_watcher: None,
+            event_indicator: None,
+            cursor_confinement: None,
+            cursor_hidden: false,
+            mouse_relative: MouseRelativeMode::Always,
+            coalesce_mouse_moves: false,
+            group_file_drops: false,
+            drop_position: None,
+            key_repeat: false,
+            text_input: true,
+            mouse_motion: true,
+            physical_keys: false,
+            modifiers: glfw::Modifiers::empty(),
+            scroll_settings: ScrollSettings::default(),
+            scroll_remainder: [0.0, 0.0],
+            confine_to_window: false,
+            swap_interval,
+            opengl,
+            drag_regions: Vec::new(),
+            resize_border: None,
+            window_drag: None,
+            windowed_rect: None,
+            refresh_rate: glfw_settings.refresh_rate,
+        };
+        if settings.get_fullscreen() {
+            window.set_fullscreen_on(MonitorId::Primary);
+        }
+        if glfw_settings.initial_state == InitialState::Iconified {
+            window.iconify();
+        }
+        Ok(window)
     }
 
-    fn flush_messages(&mut self) {
-        for (_, event) in glfw::flush_messages(&self.events) {
-            match event {
-                glfw::WindowEvent::Key(glfw::Key::Escape, _, glfw::Action::Press, _)
-                    if self.exit_on_esc =>
-                {
-                    self.window.set_should_close(true);
-                }
-                glfw::WindowEvent::Close => {
-                    if !self.automatic_close {
-                        self.window.set_should_close(false);
-                    }
-                    self.event_queue.push_back(Input::Close(CloseArgs));
-                }
-                glfw::WindowEvent::Char(ch) => {
-                    self.event_queue.push_back(Input::Text(ch.to_string()));
-                }
-                glfw::WindowEvent::Key(key, scancode, glfw::Action::Press, _) => {
-                    self.event_queue.push_back(Input::Button(ButtonArgs {
-                        state: ButtonState::Press,
-                        button: Button::Keyboard(glfw_map_key(key)),
-                        scancode: Some(scancode as i32),
-                    }));
-                }
-                glfw::WindowEvent::Key(key, scancode, glfw::Action::Release, _) => {
-                    self.event_queue.push_back(Input::Button(ButtonArgs {
-                        state: ButtonState::Release,
-                        button: Button::Keyboard(glfw_map_key(key)),
-                        scancode: Some(scancode as i32),
-                    }));
-                }
-                glfw::WindowEvent::MouseButton(button, glfw::Action::Press, _) => {
-                    self.event_queue.push_back(Input::Button(ButtonArgs {
-                        state: ButtonState::Press,
-                        button: Button::Mouse(glfw_map_mouse(button)),
-                        scancode: None,
-                    }));
-                }
-                glfw::WindowEvent::MouseButton(button, glfw::Action::Release, _) => {
-                    self.event_queue.push_back(Input::Button(ButtonArgs {
-                        state: ButtonState::Release,
-                        button: Button::Mouse(glfw_map_mouse(button)),
-                        scancode: None,
-                    }));
+    /// Returns the id of the window.
+    ///
+    /// Piston events have no field for the window they come from.
+    /// Applications with several windows can route events by the window
+    /// they were polled from, or by `Subscription::window_id`.
+    pub fn id(&self) -> WindowId {
+        self.id
+    }
+
+    /// Returns the platform GLFW selected.
+    ///
+    /// GLFW 3.3 picks the platform when it is compiled,
+    /// so on Linux this is `Wayland` when the `wayland` feature is enabled
+    /// and `X11` otherwise.
+    pub fn platform(&self) -> Platform {
+        if cfg!(target_os = "windows") {
+            Platform::Win32
+        } else if cfg!(target_os = "macos") {
+            Platform::Cocoa
+        } else if cfg!(feature = "wayland") {
+            Platform::Wayland
+        } else if cfg!(unix) {
+            Platform::X11
+        } else {
+            Platform::Null
+        }
+    }
+
+    /// Returns the Vulkan instance extensions needed to create window surfaces.
+    ///
+    /// Returns `None` when Vulkan is not available.
+    #[cfg(feature = "vulkan")]
+    pub fn required_instance_extensions(&self) -> Option<Vec<String>> {
+        self.glfw.get_required_instance_extensions()
+    }
+
+    /// Creates a Vulkan surface for the window.
+    ///
+    /// The window must be created with the Vulkan graphics API in `WindowSettings`,
+    /// and the instance with the extensions from `required_instance_extensions`.
+    /// The surface must be destroyed before the window.
+    #[cfg(feature = "vulkan")]
+    pub fn create_surface(
+        &self,
+        instance: ash::vk::Instance,
+    ) -> Result<ash::vk::SurfaceKHR, ash::vk::Result> {
+        let mut surface = ash::vk::SurfaceKHR::null();
+        match self
+            .window
+            .create_window_surface(instance, ptr::null(), &mut surface)
+        {
+            ash::vk::Result::SUCCESS => Ok(surface),
+            err => Err(err),
+        }
+    }
+
+    /// Returns the native handle of the window.
+    ///
+    /// Returns `None` on platforms GLFW has no native access for.
+    #[allow(unreachable_code)]
+    pub fn native_handle(&self) -> Option<NativeHandle> {
+        #[cfg(target_os = "windows")]
+        return Some(NativeHandle::Win32 {
+            hwnd: self.window.get_win32_window(),
+        });
+        #[cfg(target_os = "macos")]
+        return Some(NativeHandle::Cocoa {
+            ns_window: self.window.get_cocoa_window(),
+        });
+        #[cfg(all(target_os = "linux", not(feature = "wayland")))]
+        return Some(NativeHandle::X11 {
+            window: self.window.get_x11_window() as usize as u64,
+            display: self.glfw.get_x11_display(),
+        });
+        #[cfg(all(target_os = "linux", feature = "wayland"))]
+        return Some(NativeHandle::Wayland {
+            surface: self.window.get_wayland_window(),
+            display: self.glfw.get_wayland_display(),
+        });
+        None
+    }
+
+    /// Returns the events waiting in the event queue, without polling for new events.
+    pub fn pending_events(&self) -> vec_deque::Iter<'_, Event> {
+        self.event_queue.iter()
+    }
+
+    /// Returns the next event in the event queue without removing it.
+    ///
+    /// This does not poll for new events.
+    pub fn peek_event(&self) -> Option<&Event> {
+        self.event_queue.front()
+    }
+
+    /// Removes all events from the event queue.
+    ///
+    /// Use this to drop stale input, for example after a long loading screen.
+    /// This does not poll for new events, so events GLFW has not reported yet are kept.
+    pub fn clear_events(&mut self) {
+        self.event_queue.clear();
+    }
+
+    /// Subscribes to a copy of the event stream.
+    ///
+    /// Every input event queued after this call is also sent to the subscription.
+    /// Dropping the subscription unsubscribes it.
+    pub fn subscribe(&mut self) -> Subscription {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        Subscription {
+            receiver,
+            window_id: self.id,
+        }
+    }
+
+    /// Sets a filter that sees every input event before it is queued.
+    ///
+    /// The filter can change the input, and drops it by returning `false`.
+    /// Custom events are not filtered.
+    pub fn set_event_filter(&mut self, filter: Option<EventFilter>) {
+        self.event_filter = filter;
+    }
+
+    /// Shows the frame rate and frame time in the title.
+    ///
+    /// The title is updated at the given interval from the frames presented by `swap_buffers`.
+    /// Pass `None` to turn it off and restore the title.
+    pub fn set_fps_in_title(&mut self, interval: Option<Duration>) {
+        match interval {
+            Some(interval) => self.fps_in_title = Some(FpsCounter::new(interval)),
+            None => {
+                if self.fps_in_title.take().is_some() {
+                    self.window.set_title(&self.title);
+                }
+            }
+        }
+    }
+
+    /// Gets the interval at which the frame rate is shown in the title.
+    pub fn get_fps_in_title(&self) -> Option<Duration> {
+        self.fps_in_title.as_ref().map(|counter| counter.interval)
+    }
+
+    /// Sets whether event statistics are collected, for profiling input handling.
+    ///
+    /// Enabling this resets the statistics.
+    pub fn set_event_metrics(&mut self, value: bool) {
+        self.metrics = if value {
+            Some(MetricsCollector::new())
+        } else {
+            None
+        };
+    }
+
+    /// Returns the event statistics, if they are collected.
+    pub fn event_metrics(&self) -> Option<&EventMetrics> {
+        self.metrics.as_ref().map(|collector| &collector.metrics)
+    }
+
+    /// Returns how every key maps on the current keyboard layout.
+    ///
+    /// Useful for listing rebindable keys with their localized names.
+    pub fn keymap(&self) -> Vec<KeymapEntry> {
+        KEYS.iter()
+            .map(|&glfw_key| KeymapEntry {
+                glfw_key,
+                key: match glfw_key.get_scancode() {
+                    Some(scancode) => glfw_map_key_in_layout(glfw_key, scancode),
+                    None => glfw_map_key(glfw_key),
+                },
+                scancode: glfw_key.get_scancode(),
+                name: glfw_key.get_name(),
+            })
+            .collect()
+    }
+
+    /// Returns the name of a printable key on the current keyboard layout.
+    ///
+    /// The scancode from a button event is used when available,
+    /// otherwise the key is looked up in the `keymap`.
+    /// Useful for showing key bindings, for example "Z" instead of "Y" on German keyboards.
+    pub fn key_name(&self, key: keyboard::Key, scancode: Option<i32>) -> Option<String> {
+        match scancode {
+            Some(scancode) => glfw::get_key_name(None, Some(scancode)),
+            None => self
+                .keymap()
+                .into_iter()
+                .find(|entry| entry.key == key)
+                .and_then(|entry| entry.name),
+        }
+    }
+
+    /// Closes the window and releases its OpenGL context.
+    ///
+    /// Subscribers and the forwarding channel receive a close event before the window is destroyed.
+    /// The buffers used by the frame callback are deleted while the context is still current.
+    /// Returns the error GLFW reported while releasing the window, if any.
+    /// OpenGL objects created by the application are not checked, so delete them before closing.
+    pub fn close(mut self) -> Result<(), glfw::Error> {
+        self.queue_event(Event::Input(Input::Close(CloseArgs), None));
+        if self.frame_reader.is_some() {
+            self.window.make_current();
+            self.clear_frame_callback();
+        }
+        let GlfwWindow {
+            window, mut glfw, ..
+        } = self;
+        // Clear errors that happened before closing.
+        glfw::get_error();
+        if window.is_current() {
+            glfw.make_context_current(None);
+        }
+        drop(window);
+        match glfw::get_error() {
+            glfw::Error::NoError => Ok(()),
+            err => Err(err),
+        }
+    }
+
+    /// Moves a virtual cursor with a controller stick.
+    ///
+    /// The stick of the selected controller moves the system cursor and emits mouse cursor events,
+    /// and its configured buttons are reported as mouse buttons,
+    /// so mouse-oriented UIs can be used with a controller.
+    /// The cursor only moves when events are polled, so use `wait_event_timeout`
+    /// instead of `wait_event`, which blocks until GLFW reports an event.
+    /// Pass `None` to turn it off.
+    pub fn set_controller_cursor(&mut self, value: Option<ControllerCursor>) {
+        self.controller_cursor = value;
+        self.last_controller_cursor_update = None;
+    }
+
+    /// Gets the settings for moving a virtual cursor with a controller.
+    pub fn get_controller_cursor(&self) -> Option<ControllerCursor> {
+        self.controller_cursor
+    }
+
+    /// Enables sticky modifiers.
+    ///
+    /// Tapping Shift, Ctrl, Alt or Super latches it for the next key press,
+    /// which is then reported as if the modifier was held down.
+    /// Tapping a latched modifier again unlatches it.
+    pub fn set_sticky_modifiers(&mut self, value: bool) {
+        self.sticky_modifiers = if value {
+            Some(StickyModifiers::new())
+        } else {
+            None
+        };
+    }
+
+    /// Gets whether sticky modifiers are enabled.
+    pub fn get_sticky_modifiers(&self) -> bool {
+        self.sticky_modifiers.is_some()
+    }
+
+    /// Enables sticky keys.
+    ///
+    /// A key that is pressed and released between two polls
+    /// is then still seen as pressed by `is_key_pressed`
+    /// until it has been polled once. Key events are reported either way.
+    pub fn set_sticky_keys(&mut self, value: bool) {
+        self.window.set_sticky_keys(value);
+    }
+
+    /// Gets whether sticky keys are enabled.
+    pub fn get_sticky_keys(&self) -> bool {
+        self.window.has_sticky_keys()
+    }
+
+    /// Enables sticky mouse buttons.
+    ///
+    /// A mouse button that is pressed and released between two polls
+    /// is then still seen as pressed by `is_mouse_button_down`
+    /// until it has been polled once. Button events are reported either way.
+    pub fn set_sticky_mouse_buttons(&mut self, value: bool) {
+        self.window.set_sticky_mouse_buttons(value);
+    }
+
+    /// Gets whether sticky mouse buttons are enabled.
+    pub fn get_sticky_mouse_buttons(&self) -> bool {
+        self.window.has_sticky_mouse_buttons()
+    }
+
+    /// Returns the events of the window as an asynchronous stream.
+    #[cfg(feature = "async")]
+    pub fn event_stream(&mut self) -> EventStream<'_> {
+        EventStream {
+            window: self,
+            timeout: Duration::from_millis(1),
+        }
+    }
+
+    /// Returns the errors GLFW reported since the last call.
+    ///
+    /// GLFW has a single error callback, so every window
+    /// on the thread receives the errors of all windows.
+    pub fn glfw_errors(&self) -> mpsc::TryIter<'_, GlfwError> {
+        self.glfw_errors.try_iter()
+    }
+
+    /// Returns a handle for queuing input events from other threads.
+    pub fn event_sender(&self) -> EventSender {
+        EventSender {
+            sender: self.injected_events.0.clone(),
+        }
+    }
+
+    /// Returns a handle for waking up the window from other threads.
+    pub fn waker(&self) -> Waker {
+        Waker {
+            woken: self.woken.clone(),
+        }
+    }
+
+    /// Sends events over a channel instead of queuing them.
+    ///
+    /// This lets another thread handle input while the main thread keeps pumping events
+    /// with `pump_events`, since GLFW requires events to be processed on the main thread.
+    /// Events are queued again when the receiver is dropped.
+    pub fn forward_events(&mut self) -> mpsc::Receiver<Event> {
+        let (sender, receiver) = mpsc::channel();
+        self.event_sender = Some(sender);
+        receiver
+    }
+
+    /// Stops sending events over a channel and queues them again.
+    pub fn stop_forwarding_events(&mut self) {
+        self.event_sender = None;
+    }
+
+    /// Processes pending GLFW events without blocking.
+    ///
+    /// Translated events are queued, or forwarded when `forward_events` is used.
+    pub fn pump_events(&mut self) {
+        self.glfw.poll_events();
+        self.flush_messages();
+    }
+
+    /// Processes pending GLFW events and moves all queued events to the end of a vector.
+    ///
+    /// Unlike `poll_event`, which only polls GLFW when the queue is empty,
+    /// this always polls first, so the batch includes everything reported so far.
+    /// Useful for game loops that handle events in a batch.
+    /// Returns the number of events added.
+    pub fn poll_events_into(&mut self, events: &mut Vec<Event>) -> usize {
+        self.pump_events();
+        let count = self.event_queue.len();
+        events.extend(self.event_queue.drain(..));
+        count
+    }
+
+    /// Reads the pixels of the back buffer.
+    ///
+    /// Call this after rendering and before `swap_buffers`.
+    /// Returns an error for windows without an OpenGL context.
+    pub fn capture_frame(&mut self) -> io::Result<FrameCapture> {
+        self.require_opengl()?;
+        let (w, h) = self.window.get_framebuffer_size();
+        let (width, height) = (w.max(0) as u32, h.max(0) as u32);
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                w,
+                h,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+        }
+        Ok(FrameCapture {
+            width,
+            height,
+            pixels: flip_rows(&pixels, width),
+        })
+    }
+
+    fn require_opengl(&self) -> io::Result<()> {
+        if self.opengl {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the window has no OpenGL context",
+            ))
+        }
+    }
+
+    /// Calls a closure with the pixels of every presented frame.
+    ///
+    /// Frames are read back asynchronously with pixel buffer objects,
+    /// so each frame is delivered one `swap_buffers` call late.
+    /// Requires OpenGL 2.1 or later, and returns an error for windows without an OpenGL context.
+    pub fn set_frame_callback<F>(&mut self, callback: F) -> io::Result<()>
+    where
+        F: FnMut(&FrameCapture) + 'static,
+    {
+        self.require_opengl()?;
+        self.clear_frame_callback();
+        self.frame_reader = Some(FrameReader::new(Box::new(callback)));
+        Ok(())
+    }
+
+    /// Removes the closure called with the pixels of every presented frame.
+    pub fn clear_frame_callback(&mut self) {
+        if let Some(reader) = self.frame_reader.take() {
+            reader.delete();
+        }
+    }
+
+    /// Saves a screenshot when a hotkey is pressed.
+    ///
+    /// The frame is saved as a timestamped TGA file when the next frame is presented,
+    /// followed by a `SCREENSHOT` custom event.
+    /// Pass `None` to turn it off.
+    pub fn set_screenshot_hotkey(&mut self, value: Option<ScreenshotHotkey>) {
+        self.screenshot_hotkey = value;
+    }
+
+    /// Gets the settings for saving screenshots with a hotkey.
+    pub fn get_screenshot_hotkey(&self) -> Option<&ScreenshotHotkey> {
+        self.screenshot_hotkey.as_ref()
+    }
+
+    fn is_screenshot_key(&self, key: glfw::Key, scancode: glfw::Scancode) -> bool {
+        match self.screenshot_hotkey {
+            Some(ref hotkey) => hotkey.key == self.map_key(key, scancode),
+            None => false,
+        }
+    }
+
+    fn save_screenshot(&mut self) {
+        let dir = match self.screenshot_hotkey {
+            Some(ref hotkey) => hotkey.dir.clone(),
+            None => return,
+        };
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() * 1000 + d.subsec_millis() as u64)
+            .unwrap_or(0);
+        let path = dir.join(format!("screenshot-{}.tga", millis));
+        let result = self
+            .capture_frame()
+            .and_then(|frame| {
+                fs::create_dir_all(&dir)?;
+                frame.save_tga(&path)
+            })
+            .map(|_| path);
+        self.queue_event(Event::Custom(SCREENSHOT, Arc::new(result), None));
+    }
+
+    /// Delivers the clipboard as a text event on paste.
+    ///
+    /// When enabled, Ctrl+V (Cmd+V on macOS) is intercepted and the clipboard contents
+    /// are emitted as `Input::Text`, with line endings normalized to `\n`.
+    pub fn set_paste_as_text(&mut self, value: bool) {
+        self.paste_as_text = value;
+    }
+
+    /// Gets whether the clipboard is delivered as a text event on paste.
+    pub fn get_paste_as_text(&self) -> bool {
+        self.paste_as_text
+    }
+
+    fn is_paste_shortcut(
+        &self,
+        key: glfw::Key,
+        scancode: glfw::Scancode,
+        mods: glfw::Modifiers,
+    ) -> bool {
+        let modifier = if cfg!(target_os = "macos") {
+            glfw::Modifiers::Super
+        } else {
+            glfw::Modifiers::Control
+        };
+        self.paste_as_text
+            && self.text_input
+            && self.map_key(key, scancode) == keyboard::Key::V
+            && mods.contains(modifier)
+    }
+
+    /// Watches a configuration file and applies changes while running.
+    ///
+    /// The file contains `key = value` lines, with `#` starting a comment.
+    /// Changes to `title`, `width`, `height`, `vsync`, `deadzone` and `fullscreen`
+    /// are applied directly, with `fullscreen = true` using the monitor the window is on.
+    /// Values that are not valid, such as a width that is not a positive number,
+    /// are reported with a `CONFIG_INVALID` custom event.
+    /// Other changes are reported with a `CONFIG_CHANGED` custom event.
+    /// The file is checked for modifications about twice per second while events are processed.
+    pub fn watch_config<P: Into<PathBuf>>(&mut self, path: P) {
+        self.config_watcher = Some(ConfigWatcher::new(path.into()));
+    }
+
+    /// Stops watching the configuration file.
+    pub fn unwatch_config(&mut self) {
+        self.config_watcher = None;
+    }
+
+    fn reload_config(&mut self) {
+        let changes = match self.config_watcher {
+            Some(ref mut watcher) => watcher.changes(),
+            None => return,
+        };
+        let mut unapplied = vec![];
+        let mut invalid = vec![];
+        let mut size = self.size();
+        let mut fullscreen = None;
+        for (key, value) in changes {
+            let number = value.parse::<f64>().ok().filter(|n| n.is_finite());
+            let flag = match &value[..] {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            };
+            match (&key[..], number, flag) {
+                ("title", _, _) => self.set_title(value),
+                ("width", Some(width), _) if width > 0.0 => size.width = width,
+                ("height", Some(height), _) if height > 0.0 => size.height = height,
+                ("deadzone", Some(deadzone), _) if deadzone >= 0.0 => {
+                    self.joystick_deadzone = deadzone
+                }
+                ("vsync", _, Some(vsync)) => {
+                    self.set_vsync(if vsync {
+                        glfw::SwapInterval::Sync(1)
+                    } else {
+                        glfw::SwapInterval::None
+                    });
+                }
+                ("fullscreen", _, Some(value)) => fullscreen = Some(value),
+                ("width", _, _)
+                | ("height", _, _)
+                | ("deadzone", _, _)
+                | ("vsync", _, _)
+                | ("fullscreen", _, _) => invalid.push((key, value)),
+                _ => unapplied.push((key, value)),
+            }
+        }
+        if size != self.size() {
+            self.set_size(size);
+        }
+        match fullscreen {
+            Some(true) if !self.is_fullscreen() => {
+                let monitor = self.current_monitor().unwrap_or(MonitorId::Primary);
+                self.set_fullscreen_on(monitor);
+            }
+            Some(false) => self.set_windowed(),
+            _ => {}
+        }
+        if !unapplied.is_empty() {
+            self.event_queue
+                .push_back(Event::Custom(CONFIG_CHANGED, Arc::new(unapplied), None));
+        }
+        if !invalid.is_empty() {
+            self.event_queue
+                .push_back(Event::Custom(CONFIG_INVALID, Arc::new(invalid), None));
+        }
+    }
+
+    /// Enables a title-bar event indicator toggled by a hotkey.
+    ///
+    /// The back-end keeps a log of the latest translated events, available from `event_log`.
+    /// The indicator is a small window that shows the number of logged events
+    /// and the latest event in its title bar.
+    /// It draws nothing in the window, and shows no scrolling log or frame statistics.
+    /// Pass `None` to turn it off.
+    pub fn set_event_indicator_hotkey(&mut self, hotkey: Option<keyboard::Key>) {
+        self.event_indicator = hotkey.map(EventIndicator::new);
+    }
+
+    /// Returns the logged events, oldest first.
+    pub fn event_log(&self) -> Option<&VecDeque<String>> {
+        self.event_indicator
+            .as_ref()
+            .map(|indicator| &indicator.log)
+    }
+
+    fn is_event_indicator_key(&self, key: glfw::Key, scancode: glfw::Scancode) -> bool {
+        match self.event_indicator {
+            Some(ref indicator) => indicator.hotkey == self.map_key(key, scancode),
+            None => false,
+        }
+    }
+
+    fn toggle_event_indicator(&mut self) {
+        let indicator = match self.event_indicator {
+            Some(ref mut indicator) => indicator,
+            None => return,
+        };
+        if indicator.window.take().is_none() {
+            // Hints set when creating the main window would carry over.
+            self.glfw.default_window_hints();
+            // The indicator only uses its title bar, so it needs no context.
+            self.glfw
+                .window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::NoApi));
+            indicator.window =
+                self.glfw
+                    .create_window(480, 32, "Event log", glfw::WindowMode::Windowed);
+            if let Some((ref mut window, _)) = indicator.window {
+                window.set_close_polling(true);
+            }
+        }
+    }
+
+    /// Sets whether the cursor is visible over the window.
+    ///
+    /// Unlike capturing the cursor, a hidden cursor can still leave the window.
+    /// While the cursor is captured, the change takes effect when it is released.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor_hidden = !visible;
+        if self.window.get_cursor_mode() != glfw::CursorMode::Disabled {
+            let mode = self.released_cursor_mode();
+            self.window.set_cursor_mode(mode);
+        }
+    }
+
+    /// Gets whether the cursor is visible over the window when not captured.
+    pub fn get_cursor_visible(&self) -> bool {
+        !self.cursor_hidden
+    }
+
+    fn released_cursor_mode(&self) -> glfw::CursorMode {
+        if self.cursor_hidden {
+            glfw::CursorMode::Hidden
+        } else {
+            glfw::CursorMode::Normal
+        }
+    }
+
+    /// Sets when `Motion::MouseRelative` events are generated from cursor movement.
+    pub fn set_mouse_relative_mode(&mut self, mode: MouseRelativeMode) {
+        self.mouse_relative = mode;
+    }
+
+    /// Gets when `Motion::MouseRelative` events are generated from cursor movement.
+    pub fn get_mouse_relative_mode(&self) -> MouseRelativeMode {
+        self.mouse_relative
+    }
+
+    /// Sets whether held keys repeating are reported as additional key presses.
+    ///
+    /// This is useful for navigating text with held keys.
+    pub fn set_key_repeat(&mut self, value: bool) {
+        self.key_repeat = value;
+    }
+
+    /// Gets whether held keys repeating are reported as additional key presses.
+    pub fn get_key_repeat(&self) -> bool {
+        self.key_repeat
+    }
+
+    /// Sets the key binding that closes the window.
+    ///
+    /// `exit_on_esc` is the same as using `Key::Escape` without modifiers.
+    pub fn set_exit_shortcut(&mut self, value: Option<Keybinding>) {
+        self.exit_shortcut = value;
+    }
+
+    /// Gets the key binding that closes the window.
+    pub fn get_exit_shortcut(&self) -> Option<Keybinding> {
+        self.exit_shortcut
+    }
+
+    fn is_exit_shortcut(
+        &self,
+        key: glfw::Key,
+        scancode: glfw::Scancode,
+        mods: glfw::Modifiers,
+    ) -> bool {
+        match self.exit_shortcut {
+            Some(binding) => binding.matches(self.map_key(key, scancode), mods),
+            None => false,
+        }
+    }
+
+    /// Sets whether `Input::Text` events are reported.
+    ///
+    /// Enable this while a text field has focus, and disable it during gameplay
+    /// so only button events are reported. Enabled by default.
+    /// `CHAR_MODIFIERS` events are only reported while text input is enabled.
+    ///
+    /// Dead keys and compose sequences are always composed by the operating system
+    /// before text is reported, since GLFW does not report them separately.
+    /// On Windows and macOS this follows the system keyboard layout.
+    /// On X11 it goes through the X input method, so `XMODIFIERS` and the locale
+    /// decide whether dead keys work. Wayland uses the compose rules of the locale.
+    pub fn set_text_input_enabled(&mut self, value: bool) {
+        self.text_input = value;
+        self.update_polling();
+    }
+
+    /// Gets whether `Input::Text` events are reported.
+    pub fn get_text_input_enabled(&self) -> bool {
+        self.text_input
+    }
+
+    /// Sets whether keys are reported by their physical position.
+    ///
+    /// Keys are then named by their position on a US keyboard, regardless of layout,
+    /// so WASD bindings stay in place on AZERTY or Dvorak keyboards.
+    /// The symbolic key can be looked up with `key_name` using the scancode of the event.
+    pub fn set_physical_keys(&mut self, value: bool) {
+        self.physical_keys = value;
+    }
+
+    /// Gets whether keys are reported by their physical position.
+    pub fn get_physical_keys(&self) -> bool {
+        self.physical_keys
+    }
+
+    fn map_key(&self, key: glfw::Key, scancode: glfw::Scancode) -> keyboard::Key {
+        if self.physical_keys {
+            glfw_map_key(key)
+        } else {
+            glfw_map_key_in_layout(key, scancode)
+        }
+    }
+
+    /// Maps a key like `map_key`, looking up its scancode.
+    fn map_glfw_key(&self, key: glfw::Key) -> keyboard::Key {
+        match key.get_scancode() {
+            Some(scancode) => self.map_key(key, scancode),
+            None => glfw_map_key(key),
+        }
+    }
+
+    /// Returns `true` if a key is held down.
+    ///
+    /// Keys are matched the same way as in button events.
+    pub fn is_key_pressed(&self, key: keyboard::Key) -> bool {
+        KEYS.iter().any(|&glfw_key| {
+            self.map_glfw_key(glfw_key) == key
+                && self.window.get_key(glfw_key) == glfw::Action::Press
+        })
+    }
+
+    /// Returns the keys held down, mapped the same way as in button events.
+    ///
+    /// Useful for debug overlays or recording input.
+    pub fn pressed_keys(&self) -> Vec<keyboard::Key> {
+        KEYS.iter()
+            .filter(|&&glfw_key| self.window.get_key(glfw_key) == glfw::Action::Press)
+            .map(|&glfw_key| self.map_glfw_key(glfw_key))
+            .collect()
+    }
+
+    /// Returns the modifier keys held down, as of the last key or mouse button event.
+    ///
+    /// This distinguishes for example a click from a Ctrl+click.
+    /// The state of Caps Lock and Num Lock is included.
+    pub fn modifiers(&self) -> glfw::Modifiers {
+        self.modifiers
+    }
+
+    /// Returns `true` if Caps Lock is on, as of the last key or mouse button event.
+    pub fn caps_lock(&self) -> bool {
+        self.modifiers.contains(glfw::Modifiers::CapsLock)
+    }
+
+    /// Returns `true` if Num Lock is on, as of the last key or mouse button event.
+    pub fn num_lock(&self) -> bool {
+        self.modifiers.contains(glfw::Modifiers::NumLock)
+    }
+
+    /// Sets how scroll offsets are normalized.
+    ///
+    /// Scroll offsets differ across platforms and devices,
+    /// for example between mouse wheels and trackpads.
+    pub fn set_scroll_settings(&mut self, settings: ScrollSettings) {
+        self.scroll_settings = settings;
+        self.scroll_remainder = [0.0, 0.0];
+    }
+
+    /// Gets how scroll offsets are normalized.
+    pub fn get_scroll_settings(&self) -> ScrollSettings {
+        self.scroll_settings
+    }
+
+    /// Sets whether consecutive mouse moves are merged when polling events.
+    ///
+    /// Each run of moves becomes one `MouseCursor` event with the last position,
+    /// followed by one `MouseRelative` event with the summed motion.
+    /// This keeps high polling rate mice from flooding the event queue.
+    pub fn set_coalesce_mouse_moves(&mut self, value: bool) {
+        self.coalesce_mouse_moves = value;
+    }
+
+    /// Gets whether consecutive mouse moves are merged when polling events.
+    pub fn get_coalesce_mouse_moves(&self) -> bool {
+        self.coalesce_mouse_moves
+    }
+
+    /// Sets whether files dropped together are reported in one `FILE_DROP` event.
+    ///
+    /// Otherwise each file is reported in its own `FileDrag::Drop` event.
+    pub fn set_group_file_drops(&mut self, value: bool) {
+        self.group_file_drops = value;
+    }
+
+    /// Gets whether files dropped together are reported in one `FILE_DROP` event.
+    pub fn get_group_file_drops(&self) -> bool {
+        self.group_file_drops
+    }
+
+    /// Returns the cursor position the last files were dropped at, in window coordinates.
+    ///
+    /// Use this with `FileDrag::Drop` events to find where files were dropped.
+    pub fn drop_position(&self) -> Option<[f64; 2]> {
+        self.drop_position
+    }
+
+    /// Sets whether mouse cursor and relative motion events are reported.
+    ///
+    /// Disable this when the mouse is not used, so GLFW does not report cursor movement
+    /// unless it is needed for confining the cursor or dragging the window.
+    /// Enabled by default.
+    pub fn set_mouse_motion_enabled(&mut self, value: bool) {
+        self.mouse_motion = value;
+        // Relative motion is measured from the next position.
+        self.last_mouse_pos = None;
+        self.update_polling();
+    }
+
+    /// Gets whether mouse cursor and relative motion events are reported.
+    pub fn get_mouse_motion_enabled(&self) -> bool {
+        self.mouse_motion
+    }
+
+    fn reports_mouse_motion(&self) -> bool {
+        self.mouse_motion && !(self.pause_while_unfocused.mouse_motion && self.is_inactive())
+    }
+
+    fn is_inactive(&self) -> bool {
+        !self.window.is_focused() || self.window.is_iconified()
+    }
+
+    /// Makes GLFW report only the events that are used.
+    fn update_polling(&mut self) {
+        let reports_motion = self.reports_mouse_motion();
+        self.last_mouse_pos = tracked_mouse_pos(self.last_mouse_pos, reports_motion);
+        let cursor_pos =
+            reports_motion || self.cursor_confinement.is_some() || self.window_drag.is_some();
+        set_polling(&mut self.window, self.text_input, cursor_pos);
+    }
+
+    fn emits_mouse_relative(&self) -> bool {
+        let captured = self.window.get_cursor_mode() == glfw::CursorMode::Disabled;
+        match self.mouse_relative {
+            MouseRelativeMode::Always => true,
+            MouseRelativeMode::WhileCaptured => captured,
+            MouseRelativeMode::Raw => captured && self.window.uses_raw_mouse_motion(),
+            MouseRelativeMode::Never => false,
+        }
+    }
+
+    /// Clamps the cursor to a rectangle `[x, y, width, height]` in window coordinates.
+    ///
+    /// This is best-effort: the cursor is moved back into the rectangle when events are polled
+    /// and a cursor position inside the window is reported.
+    /// Fast motion can still carry the cursor out of the window, where GLFW stops reporting
+    /// its position, and it is only moved back once it returns.
+    /// Reported positions are always inside the rectangle.
+    /// Pass `None` to release the cursor.
+    pub fn confine_cursor_to(&mut self, rect: Option<[f64; 4]>) {
+        self.confine_to_window = false;
+        self.cursor_confinement = rect;
+        self.update_polling();
+        if let Some(rect) = rect {
+            let pos = self.window.get_cursor_pos();
+            let (x, y) = clamp_to_rect(rect, pos);
+            if (x, y) != pos {
+                self.window.set_cursor_pos(x, y);
+            }
+        }
+    }
+
+    /// Clamps the cursor to the window while keeping it visible, following resizes.
+    ///
+    /// GLFW 3.3 has no confined cursor mode, so this is the best-effort clamping
+    /// of `confine_cursor_to` and the cursor can still escape the window.
+    /// Reported cursor positions stay inside the window,
+    /// which makes it suitable for edge scrolling.
+    pub fn confine_cursor_to_window(&mut self, enabled: bool) {
+        if enabled {
+            let (w, h) = self.window.get_size();
+            self.confine_cursor_to(Some(content_rect(w, h)));
+        } else {
+            self.confine_cursor_to(None);
+        }
+        self.confine_to_window = enabled;
+    }
+
+    /// Gets whether the cursor is clamped to the window.
+    pub fn get_confine_cursor_to_window(&self) -> bool {
+        self.confine_to_window
+    }
+
+    /// Gets the rectangle the cursor is clamped to.
+    pub fn get_cursor_confinement(&self) -> Option<[f64; 4]> {
+        self.cursor_confinement
+    }
+
+    /// Sets the shape of the cursor when it is over the window.
+    pub fn set_cursor(&mut self, shape: CursorShape) {
+        self.window
+            .set_cursor(Some(glfw::Cursor::standard(shape.into())));
+    }
+
+    /// Sets a cursor image from RGBA pixels, with rows from top to bottom.
+    ///
+    /// The hotspot is the point of the image, in pixels from the upper-left corner,
+    /// that is at the cursor position. The cursor is kept alive while in use.
+    /// Returns `false` if the pixels do not match the width and height.
+    pub fn set_cursor_image(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        hotspot: [u32; 2],
+    ) -> bool {
+        if width == 0 || height == 0 || pixels.len() != width as usize * height as usize * 4 {
+            return false;
+        }
+        let image = glfw::PixelImage {
+            width,
+            height,
+            pixels: pack_rgba(pixels),
+        };
+        self.window
+            .set_cursor(Some(glfw::Cursor::create_from_pixels(
+                image, hotspot[0], hotspot[1],
+            )));
+        true
+    }
+
+    /// Returns `true` if a mouse button is held down.
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        use glfw::MouseButton::*;
+
+        [
+            Button1, Button2, Button3, Button4, Button5, Button6, Button7, Button8,
+        ]
+        .iter()
+        .any(|&b| {
+            glfw_map_mouse(b) == button && self.window.get_mouse_button(b) == glfw::Action::Press
+        })
+    }
+
+    /// Returns the position of the cursor in window coordinates.
+    pub fn cursor_position(&self) -> [f64; 2] {
+        let (x, y) = self.window.get_cursor_pos();
+        [x, y]
+    }
+
+    /// Moves the cursor to a position in window coordinates.
+    ///
+    /// The next relative mouse motion is measured from the new position,
+    /// so warping does not produce a jump in `MouseRelative` events.
+    pub fn set_cursor_pos(&mut self, x: f64, y: f64) {
+        self.window.set_cursor_pos(x, y);
+        self.last_mouse_pos = Some((x, y));
+    }
+
+    /// Starts mapping a controller to the standard gamepad layout.
+    ///
+    /// Returns `None` if there is no controller with the given id.
+    pub fn gamepad_mapping_wizard(&self, id: u32) -> Option<GamepadMappingWizard> {
+        let joystick = self.glfw.get_joystick(JoystickId::from_i32(id as i32)?);
+        if joystick.is_present() {
+            Some(GamepadMappingWizard::new(joystick))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the swap interval.
+    ///
+    /// Returns `None` for windows created with `from_pieces` until `set_vsync` is called.
+    pub fn vsync(&self) -> Option<glfw::SwapInterval> {
+        self.swap_interval
+    }
+
+    /// Sets the swap interval, making the window's context current.
+    ///
+    /// This does nothing for windows without an OpenGL context,
+    /// where the Vulkan swap chain controls vsync.
+    pub fn set_vsync(&mut self, value: glfw::SwapInterval) {
+        if !self.opengl {
+            return;
+        }
+        self.window.make_current();
+        self.glfw.set_swap_interval(value);
+        self.swap_interval = Some(value);
+    }
+
+    /// Sets the regions `[x, y, width, height]` that move the window when dragged.
+    ///
+    /// This is meant for drawing a custom title bar in undecorated windows.
+    /// Mouse events used for dragging are not reported.
+    pub fn set_drag_regions(&mut self, regions: Vec<[f64; 4]>) {
+        self.drag_regions = regions;
+    }
+
+    /// Gets the regions that move the window when dragged.
+    pub fn get_drag_regions(&self) -> &[[f64; 4]] {
+        &self.drag_regions
+    }
+
+    /// Sets the width of the window border that resizes the window when dragged.
+    ///
+    /// This is meant for undecorated windows. Pass `None` to turn it off.
+    pub fn set_resize_border(&mut self, width: Option<f64>) {
+        self.resize_border = width;
+    }
+
+    /// Gets the width of the window border that resizes the window when dragged.
+    pub fn get_resize_border(&self) -> Option<f64> {
+        self.resize_border
+    }
+
+    /// Moves the window following the cursor until the left mouse button is released.
+    ///
+    /// Call this when the left mouse button is pressed on a custom title bar.
+    /// Unlike drag regions, the release of the left mouse button is still reported,
+    /// but cursor movement is not reported while the window is dragged.
+    /// Returns `false` if the left mouse button is not held down.
+    pub fn begin_drag(&mut self) -> bool {
+        if self.window.get_mouse_button(glfw::MouseButton::Button1) != glfw::Action::Press {
+            return false;
+        }
+        let mut drag = WindowDrag::new(&self.window, DragEdges::default());
+        drag.report_release = true;
+        self.window_drag = Some(drag);
+        self.update_polling();
+        true
+    }
+
+    /// Returns the edges that are dragged from a position, if any.
+    fn drag_edges_at(&self, (x, y): (f64, f64)) -> Option<DragEdges> {
+        let (w, h) = self.window.get_size();
+        let (w, h) = (w as f64, h as f64);
+        if let Some(border) = self.resize_border {
+            let edges = DragEdges {
+                left: x < border,
+                right: x >= w - border,
+                top: y < border,
+                bottom: y >= h - border,
+            };
+            if edges.left || edges.right || edges.top || edges.bottom {
+                return Some(edges);
+            }
+        }
+        if self
+            .drag_regions
+            .iter()
+            .any(|&rect| clamp_to_rect(rect, (x, y)) == (x, y))
+        {
+            return Some(DragEdges::default());
+        }
+        None
+    }
+
+    /// Returns which optional features are available on the current platform.
+    ///
+    /// Calls for unavailable features are silently ignored by GLFW.
+    pub fn capabilities(&self) -> Capabilities {
+        let wayland = self.platform() == Platform::Wayland;
+        Capabilities {
+            window_position: !wayland,
+            transparency: self.window.is_framebuffer_transparent(),
+            raw_mouse_motion: self.glfw.supports_raw_motion(),
+            gamma: !wayland,
+            clipboard: true,
+            opacity: !wayland,
+            always_on_top: !wayland,
+        }
+    }
+
+    /// Returns the names of the connected monitors.
+    ///
+    /// The index of a name can be used with `MonitorId::Index`.
+    pub fn monitor_names(&mut self) -> Vec<String> {
+        self.glfw.with_connected_monitors(|_, monitors| {
+            monitors
+                .iter()
+                .map(|m| m.get_name().unwrap_or_default())
+                .collect()
+        })
+    }
+
+    /// Returns the content scale of a monitor, if the monitor is found.
+    ///
+    /// This is the ratio between the current DPI and the platform's default DPI,
+    /// useful for sizing text and UI on HiDPI displays.
+    pub fn monitor_content_scale(&mut self, monitor: MonitorId) -> Option<(f32, f32)> {
+        self.with_monitor(&monitor, |_, m| m.get_content_scale())
+    }
+
+    /// Returns the physical size and video mode size of a monitor, if the monitor is found.
+    pub fn monitor_size(&mut self, monitor: MonitorId) -> Option<MonitorSize> {
+        self.with_monitor(&monitor, |_, m| {
+            let (width_mm, height_mm) = m.get_physical_size();
+            let mode = m.get_video_mode()?;
+            Some(MonitorSize {
+                width_mm: width_mm.max(0) as u32,
+                height_mm: height_mm.max(0) as u32,
+                width: mode.width,
+                height: mode.height,
+            })
+        })
+        .and_then(|size| size)
+    }
+
+    /// Returns the content scale of the monitor the window is on.
+    pub fn content_scale(&self) -> (f32, f32) {
+        self.window.get_content_scale()
+    }
+
+    /// Centers the window on the monitor it is on.
+    ///
+    /// Falls back to the primary monitor when the window is not on any monitor.
+    pub fn center(&mut self) -> bool {
+        let monitor = self.current_monitor().unwrap_or(MonitorId::Primary);
+        self.center_on(monitor)
+    }
+
+    /// Centers the window within the work area of a monitor.
+    ///
+    /// Returns `false` if the monitor was not found.
+    pub fn center_on(&mut self, monitor: MonitorId) -> bool {
+        self.with_monitor(&monitor, |window, m| {
+            let (x, y, w, h) = m.get_workarea();
+            let (width, height) = window.get_size();
+            let (left, top, right, bottom) = window.get_frame_size();
+            window.set_pos(
+                x + (w - (left + width + right)) / 2 + left,
+                y + (h - (top + height + bottom)) / 2 + top,
+            );
+        })
+        .is_some()
+    }
+
+    /// Returns the monitor containing the center of the window.
+    fn current_monitor(&mut self) -> Option<MonitorId> {
+        let (x, y) = self.window.get_pos();
+        let (w, h) = self.window.get_size();
+        let (cx, cy) = (x + w / 2, y + h / 2);
+        self.glfw.with_connected_monitors(|_, monitors| {
+            monitors
+                .iter()
+                .position(|m| {
+                    let (mx, my, mw, mh) = m.get_workarea();
+                    cx >= mx && cy >= my && cx < mx + mw && cy < my + mh
+                })
+                .map(MonitorId::Index)
+        })
+    }
+
+    /// Calls a closure with the window and a monitor, if the monitor is found.
+    fn with_monitor<T, F>(&mut self, monitor: &MonitorId, f: F) -> Option<T>
+    where
+        F: FnOnce(&mut glfw::PWindow, &glfw::Monitor) -> T,
+    {
+        let window = &mut self.window;
+        match *monitor {
+            MonitorId::Primary => self
+                .glfw
+                .with_primary_monitor(|_, m| m.map(|m| f(window, m))),
+            MonitorId::Index(i) => self
+                .glfw
+                .with_connected_monitors(|_, monitors| monitors.get(i).map(|m| f(window, m))),
+            MonitorId::Name(ref name) => self.glfw.with_connected_monitors(|_, monitors| {
+                monitors
+                    .iter()
+                    .find(|m| m.get_name().as_ref() == Some(name))
+                    .map(|m| f(window, m))
+            }),
+        }
+    }
+
+    /// Makes the window fullscreen on a monitor, using its current video mode.
+    ///
+    /// When a refresh rate is set, the closest supported refresh rate
+    /// at the current resolution is used.
+    ///
+    /// Returns `false` if the monitor was not found.
+    pub fn set_fullscreen_on(&mut self, monitor: MonitorId) -> bool {
+        let refresh_rate = self.refresh_rate;
+        self.enter_fullscreen(&monitor, |m| {
+            let current = VideoMode::from(m.get_video_mode()?);
+            let refresh_rate = match refresh_rate {
+                Some(refresh_rate) => refresh_rate,
+                None => return Some(current),
+            };
+            let modes: Vec<VideoMode> = m
+                .get_video_modes()
+                .into_iter()
+                .map(VideoMode::from)
+                .filter(|mode| mode.width == current.width && mode.height == current.height)
+                .collect();
+            closest_video_mode(
+                &modes,
+                VideoMode {
+                    refresh_rate,
+                    ..current
+                },
+            )
+            .or(Some(current))
+        })
+        .is_some()
+    }
+
+    /// Sets the target refresh rate used when entering fullscreen.
+    ///
+    /// This is useful for high refresh rate displays, or for capping to 60 Hz
+    /// on variable refresh rate monitors. Takes effect the next time
+    /// `set_fullscreen_on` is called. `None` uses the monitor's current refresh rate.
+    pub fn set_refresh_rate(&mut self, refresh_rate: Option<u32>) {
+        self.refresh_rate = refresh_rate;
+    }
+
+    /// Gets the target refresh rate used when entering fullscreen.
+    pub fn get_refresh_rate(&self) -> Option<u32> {
+        self.refresh_rate
+    }
+
+    /// Returns the video modes supported by a monitor.
+    pub fn video_modes(&mut self, monitor: MonitorId) -> Vec<VideoMode> {
+        self.with_monitor(&monitor, |_, m| {
+            m.get_video_modes()
+                .into_iter()
+                .map(VideoMode::from)
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    /// Makes the window exclusively fullscreen on a monitor with a video mode.
+    ///
+    /// When the monitor does not support the mode, the closest supported mode is used.
+    /// Returns the video mode that was used, or `None` if the monitor was not found.
+    pub fn set_fullscreen_mode(
+        &mut self,
+        monitor: MonitorId,
+        mode: VideoMode,
+    ) -> Option<VideoMode> {
+        self.enter_fullscreen(&monitor, |m| {
+            let modes: Vec<VideoMode> = m
+                .get_video_modes()
+                .into_iter()
+                .map(VideoMode::from)
+                .collect();
+            closest_video_mode(&modes, mode).or_else(|| m.get_video_mode().map(VideoMode::from))
+        })
+    }
+
+    fn enter_fullscreen<F>(&mut self, monitor: &MonitorId, pick_mode: F) -> Option<VideoMode>
+    where
+        F: FnOnce(&glfw::Monitor) -> Option<VideoMode>,
+    {
+        if !self.is_fullscreen() {
+            let (x, y) = self.window.get_pos();
+            let (w, h) = self.window.get_size();
+            self.windowed_rect = Some((x, y, w, h));
+        }
+        self.with_monitor(monitor, |window, m| {
+            let mode = pick_mode(m)?;
+            window.set_monitor(
+                glfw::WindowMode::FullScreen(m),
+                0,
+                0,
+                mode.width,
+                mode.height,
+                Some(mode.refresh_rate),
+            );
+            Some(mode)
+        })
+        .and_then(|mode| mode)
+    }
+
+    /// Leaves fullscreen, restoring the previous position and size.
+    pub fn set_windowed(&mut self) {
+        if !self.is_fullscreen() {
+            return;
+        }
+        let (x, y, w, h) = match self.windowed_rect.take() {
+            Some(rect) => rect,
+            None => {
+                let (w, h) = self.window.get_size();
+                (0, 0, w, h)
+            }
+        };
+        self.window.set_monitor(
+            glfw::WindowMode::Windowed,
+            x,
+            y,
+            w.max(1) as u32,
+            h.max(1) as u32,
+            None,
+        );
+    }
+
+    /// Maximizes the window.
+    pub fn maximize(&mut self) {
+        self.window.maximize();
+    }
+
+    /// Iconifies (minimizes) the window.
+    pub fn iconify(&mut self) {
+        self.window.iconify();
+    }
+
+    /// Restores the window from being maximized or iconified.
+    pub fn restore(&mut self) {
+        self.window.restore();
+    }
+
+    /// Constrains the aspect ratio of the content area during resizes.
+    ///
+    /// For example, `set_aspect_ratio(16, 9)` keeps the window at 16:9.
+    pub fn set_aspect_ratio(&mut self, numerator: u32, denominator: u32) {
+        self.window.set_aspect_ratio(numerator, denominator);
+    }
+
+    /// Removes the aspect ratio constraint.
+    pub fn clear_aspect_ratio(&mut self) {
+        let dont_care = glfw::ffi::DONT_CARE as u32;
+        self.window.set_aspect_ratio(dont_care, dont_care);
+    }
+
+    /// Sets the minimum and maximum size of the content area.
+    ///
+    /// `None` removes the limit.
+    pub fn set_size_limits(&mut self, min: Option<Size>, max: Option<Size>) {
+        self.window.set_size_limits(
+            min.map(|size| size.width as u32),
+            min.map(|size| size.height as u32),
+            max.map(|size| size.width as u32),
+            max.map(|size| size.height as u32),
+        );
+    }
+
+    /// Loads an image file, such as a PNG or ICO, and uses it as the window icon.
+    ///
+    /// Requires the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn set_icon_from_path<P: AsRef<Path>>(&mut self, path: P) -> Result<(), image::ImageError> {
+        let icon = image::open(path)?.to_rgba8();
+        let (width, height) = icon.dimensions();
+        self.window.set_icon_from_pixels(vec![glfw::PixelImage {
+            width,
+            height,
+            pixels: pack_rgba(&icon.into_raw()),
+        }]);
+        Ok(())
+    }
+
+    /// Brings the window to the front and gives it input focus.
+    pub fn focus(&mut self) {
+        self.window.focus();
+    }
+
+    /// Requests the user's attention, for example by flashing the taskbar entry.
+    pub fn request_attention(&mut self) {
+        self.window.request_attention();
+    }
+
+    /// Sets whether the window stays on top of other windows.
+    ///
+    /// To create a window that is on top from the start, use
+    /// `GlfwSettings::always_on_top`.
+    pub fn set_always_on_top(&mut self, value: bool) {
+        self.window.set_floating(value);
+    }
+
+    /// Gets whether the window stays on top of other windows.
+    pub fn get_always_on_top(&self) -> bool {
+        self.window.is_floating()
+    }
+
+    /// Returns `true` if the window is maximized.
+    pub fn is_maximized(&self) -> bool {
+        self.window.is_maximized()
+    }
+
+    /// Returns `true` if the window is iconified (minimized).
+    pub fn is_iconified(&self) -> bool {
+        self.window.is_iconified()
+    }
+
+    /// Returns `true` if the window is visible.
+    pub fn is_visible(&self) -> bool {
+        self.window.is_visible()
+    }
+
+    /// Returns `true` if the window has input focus.
+    pub fn is_focused(&self) -> bool {
+        self.window.is_focused()
+    }
+
+    /// Returns `true` if the cursor is over the content area of the window.
+    pub fn is_hovered(&self) -> bool {
+        self.window.is_hovered()
+    }
+
+    /// Returns `true` if the window is fullscreen.
+    pub fn is_fullscreen(&self) -> bool {
+        self.window.with_window_mode(|mode| match mode {
+            glfw::WindowMode::FullScreen(_) => true,
+            glfw::WindowMode::Windowed => false,
+        })
+    }
+
+    /// Sets whether controllers known to GLFW as gamepads use the standard gamepad layout.
+    ///
+    /// Buttons and axes are then numbered as in `glfw::GamepadButton` and `glfw::GamepadAxis`,
+    /// for example button 0 is A and axis 4 is the left trigger,
+    /// instead of using device specific numbers.
+    pub fn set_gamepad_mapping(&mut self, value: bool) {
+        self.gamepad_mapping = value;
+        for j in &mut self.joysticks {
+            j.set_gamepad(value);
+        }
+    }
+
+    /// Gets whether controllers known to GLFW as gamepads use the standard gamepad layout.
+    pub fn get_gamepad_mapping(&self) -> bool {
+        self.gamepad_mapping
+    }
+
+    /// Sets how the axes and buttons of a controller are remapped.
+    ///
+    /// This fixes miswired controllers before events are reported.
+    /// Pass `None` to report the controller as is.
+    pub fn set_controller_remap(&mut self, id: u32, remap: Option<ControllerRemap>) {
+        match remap {
+            Some(remap) => self.controller_remaps.insert(id, remap),
+            None => self.controller_remaps.remove(&id),
+        };
+    }
+
+    /// Gets how the axes and buttons of a controller are remapped.
+    pub fn get_controller_remap(&self, id: u32) -> Option<&ControllerRemap> {
+        self.controller_remaps.get(&id)
+    }
+
+    /// Sets the input to ignore while the window is unfocused or iconified.
+    pub fn set_pause_while_unfocused(&mut self, pause: PauseWhileUnfocused) {
+        self.pause_while_unfocused = pause;
+        self.update_polling();
+    }
+
+    /// Gets the input to ignore while the window is unfocused or iconified.
+    pub fn get_pause_while_unfocused(&self) -> PauseWhileUnfocused {
+        self.pause_while_unfocused
+    }
+
+    /// Starts recording controller events, discarding any unfinished recording.
+    ///
+    /// Events are recorded as reported, after remapping.
+    /// Replayed events are not recorded.
+    pub fn start_controller_recording(&mut self) {
+        self.controller_recording = Some((Instant::now(), ControllerRecording::default()));
+    }
+
+    /// Stops recording controller events, returning the recording.
+    pub fn stop_controller_recording(&mut self) -> Option<ControllerRecording> {
+        self.controller_recording
+            .take()
+            .map(|(_, recording)| recording)
+    }
+
+    /// Replays recorded controller events with their original timing.
+    ///
+    /// The events are added to the event queue as if the controllers reported them,
+    /// replacing any unfinished replay.
+    pub fn replay_controller_recording(&mut self, recording: ControllerRecording) {
+        self.controller_replay = Some((Instant::now(), recording, 0));
+    }
+
+    /// Returns `true` while a controller recording is being replayed.
+    pub fn is_replaying_controllers(&self) -> bool {
+        self.controller_replay.is_some()
+    }
+
+    /// Sets whether controllers keep their id when they reconnect.
+    ///
+    /// Controllers are then numbered in the order they are first seen,
+    /// and a reconnected controller gets back the id of a disconnected controller
+    /// of the same model, even if GLFW puts it in another slot.
+    /// This keeps for example the controller of player 2 assigned to player 2.
+    pub fn set_stable_controller_ids(&mut self, value: bool) {
+        if value != self.stable_controller_ids.is_some() {
+            self.stable_controller_ids = if value {
+                let mut ids = StableControllerIds {
+                    controllers: Vec::new(),
+                };
+                for &slot in JOYSTICKS.iter() {
+                    if self.glfw.get_joystick(slot).is_present() {
+                        ids.id(&self.glfw, slot);
+                    }
                 }
-                glfw::WindowEvent::CursorPos(x, y) => {
+                Some(ids)
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Gets whether controllers keep their id when they reconnect.
+    pub fn get_stable_controller_ids(&self) -> bool {
+        self.stable_controller_ids.is_some()
+    }
+
+    /// Sets the back-end used to play rumble effects.
+    pub fn set_rumble_backend(&mut self, backend: Option<Box<dyn RumbleBackend>>) {
+        self.rumble_backend = backend;
+    }
+
+    /// Rumbles a controller for a duration.
+    ///
+    /// The strengths of the low and high frequency motors are from 0 to 1.
+    /// Returns `false` if the controller is not connected, has no GUID,
+    /// or the rumble back-end does not support it.
+    pub fn set_rumble(&mut self, id: u32, low: f32, high: f32, duration: Duration) -> bool {
+        let backend = match self.rumble_backend {
+            Some(ref mut backend) => backend,
+            None => return false,
+        };
+        let slot = match self.stable_controller_ids {
+            Some(ref ids) => ids.slot(id),
+            None => JoystickId::from_i32(id as i32),
+        };
+        let guid = match slot {
+            Some(slot) => self.glfw.get_joystick(slot).get_guid(),
+            None => None,
+        };
+        match guid {
+            Some(guid) => {
+                backend.rumble(&guid, low.clamp(0.0, 1.0), high.clamp(0.0, 1.0), duration)
+            }
+            None => false,
+        }
+    }
+
+    /// Sets the ids of the controllers to poll for events.
+    ///
+    /// By default all 16 controller slots are polled, or none when controllers
+    /// are disabled in the window settings. Ids above 15 are ignored.
+    pub fn set_controller_ids(&mut self, ids: &[u32]) {
+        self.joysticks = ids
+            .iter()
+            .filter_map(|&id| JoystickId::from_i32(id as i32))
+            .map(|id| {
+                let mut j = JoystickHelper::new(self.glfw.get_joystick(id));
+                j.set_gamepad(self.gamepad_mapping);
+                j.set_normalize_triggers(self.normalize_triggers);
+                j
+            })
+            .collect();
+    }
+
+    /// Gets the ids of the controllers polled for events.
+    pub fn get_controller_ids(&self) -> Vec<u32> {
+        self.joysticks
+            .iter()
+            .map(|j| j.joystick.id as u32)
+            .collect()
+    }
+
+    /// Returns the connected controllers.
+    ///
+    /// With stable controller ids, a controller connected since events were last polled
+    /// is listed once the connection has been polled.
+    pub fn controllers(&self) -> Vec<ControllerInfo> {
+        JOYSTICKS
+            .iter()
+            .map(|&id| self.glfw.get_joystick(id))
+            .filter(|joystick| joystick.is_present())
+            .filter_map(|joystick| {
+                let id = match self.stable_controller_ids {
+                    Some(ref ids) => ids.get(joystick.id)?,
+                    None => joystick.id as u32,
+                };
+                Some(ControllerInfo {
+                    id,
+                    name: joystick.get_name(),
+                    guid: joystick.get_guid(),
+                    gamepad_name: joystick.get_gamepad_name(),
+                })
+            })
+            .collect()
+    }
+
+    /// Sets whether gamepad triggers are reported from 0 (released) to 1 (fully pressed).
+    ///
+    /// GLFW reports triggers from -1 to 1. This only applies to controllers
+    /// using the standard gamepad layout, see `set_gamepad_mapping`.
+    pub fn set_normalize_triggers(&mut self, value: bool) {
+        self.normalize_triggers = value;
+        for j in &mut self.joysticks {
+            j.set_normalize_triggers(value);
+        }
+    }
+
+    /// Gets whether gamepad triggers are reported from 0 to 1.
+    pub fn get_normalize_triggers(&self) -> bool {
+        self.normalize_triggers
+    }
+
+    /// Adds gamepad mappings in the SDL_GameControllerDB format.
+    ///
+    /// This lets controllers GLFW does not know about use the standard gamepad layout.
+    /// Returns `false` if the mappings could not be parsed.
+    pub fn update_gamepad_mappings(&mut self, mappings: &str) -> bool {
+        let ok = self.glfw.update_gamepad_mappings(mappings);
+        // Controllers may have become gamepads, so read them again.
+        for j in &mut self.joysticks {
+            j.connected = false;
+        }
+        ok
+    }
+
+    /// Adds gamepad mappings from a file in the SDL_GameControllerDB format,
+    /// such as `gamecontrollerdb.txt`.
+    pub fn update_gamepad_mappings_from_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> io::Result<bool> {
+        let mappings = fs::read_to_string(path)?;
+        Ok(self.update_gamepad_mappings(&mappings))
+    }
+
+    /// Sets whether Alt+Enter toggles fullscreen on the primary monitor.
+    pub fn set_toggle_fullscreen_on_alt_enter(&mut self, value: bool) {
+        self.toggle_fullscreen_on_alt_enter = value;
+    }
+
+    /// Gets whether Alt+Enter toggles fullscreen on the primary monitor.
+    pub fn get_toggle_fullscreen_on_alt_enter(&self) -> bool {
+        self.toggle_fullscreen_on_alt_enter
+    }
+
+    fn is_fullscreen_shortcut(&self, key: glfw::Key, mods: glfw::Modifiers) -> bool {
+        self.toggle_fullscreen_on_alt_enter
+            && key == glfw::Key::Enter
+            && mods.contains(glfw::Modifiers::Alt)
+    }
+
+    fn flush_messages(&mut self) {
+        let started = self.metrics.as_ref().map(|_| Instant::now());
+        let first_new = self.event_queue.len();
+        let was_dragging = self.window_drag.is_some();
+        let mut focus_changed = false;
+        let mut toggle_event_indicator = false;
+        let mut toggle_fullscreen = false;
+        for (time, event) in glfw::flush_messages(&self.events) {
+            let stamp = Some(timestamp(time));
+            trace!("GLFW event: {:?}", event);
+            match event {
+                glfw::WindowEvent::Key(key, _, action, mods) => {
+                    // The modifiers of a modifier key event do not include
+                    // the change made by the key itself on all platforms.
+                    self.modifiers = mods;
+                    if let Some(modifier) = key_modifier(key) {
+                        self.modifiers
+                            .set(modifier, action != glfw::Action::Release);
+                    }
+                }
+                glfw::WindowEvent::MouseButton(_, _, mods) => self.modifiers = mods,
+                _ => {}
+            }
+            match event {
+                glfw::WindowEvent::Key(key, scancode, glfw::Action::Press, mods)
+                    if self.is_exit_shortcut(key, scancode, mods) =>
+                {
+                    self.window.set_should_close(true);
+                }
+                glfw::WindowEvent::Key(key, scancode, glfw::Action::Press, _)
+                    if self.is_screenshot_key(key, scancode) =>
+                {
+                    self.screenshot_requested = true;
+                }
+                glfw::WindowEvent::Key(key, scancode, _, _)
+                    if self.is_screenshot_key(key, scancode) => {}
+                glfw::WindowEvent::Key(key, scancode, glfw::Action::Press, _)
+                    if self.is_event_indicator_key(key, scancode) =>
+                {
+                    toggle_event_indicator = true;
+                }
+                glfw::WindowEvent::Key(key, scancode, _, _)
+                    if self.is_event_indicator_key(key, scancode) => {}
+                glfw::WindowEvent::Key(key, _, glfw::Action::Press, mods)
+                    if self.is_fullscreen_shortcut(key, mods) =>
+                {
+                    toggle_fullscreen = true;
+                }
+                glfw::WindowEvent::Key(key, _, _, mods)
+                    if self.is_fullscreen_shortcut(key, mods) => {}
+                glfw::WindowEvent::Key(key, scancode, glfw::Action::Press, mods)
+                    if self.is_paste_shortcut(key, scancode, mods) =>
+                {
+                    self.paste_key_down = true;
+                    if let Some(text) = self.window.get_clipboard_string() {
+                        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+                        self.event_queue
+                            .push_back(Event::Input(Input::Text(text), stamp));
+                    }
+                }
+                glfw::WindowEvent::Key(key, scancode, glfw::Action::Release, _)
+                    if self.paste_key_down && self.map_key(key, scancode) == keyboard::Key::V =>
+                {
+                    self.paste_key_down = false;
+                }
+                glfw::WindowEvent::Close => {
+                    if !self.automatic_close {
+                        self.window.set_should_close(false);
+                    }
+                    self.event_queue
+                        .push_back(Event::Input(Input::Close(CloseArgs), stamp));
+                }
+                glfw::WindowEvent::CharModifiers(ch, mods) => {
+                    self.event_queue.push_back(Event::Custom(
+                        CHAR_MODIFIERS,
+                        Arc::new((ch, mods)),
+                        stamp,
+                    ));
+                }
+                glfw::WindowEvent::Char(ch) if !self.text_input => {
+                    debug!("Dropped character {:?}: text input is disabled", ch);
+                }
+                glfw::WindowEvent::Char(ch) => {
                     self.event_queue
-                        .push_back(Input::Move(Motion::MouseCursor([x, y])));
+                        .push_back(Event::Input(Input::Text(ch.to_string()), stamp));
+                }
+                glfw::WindowEvent::Key(key, scancode, glfw::Action::Press, _) => {
+                    if let Some(ref mut sticky) = self.sticky_modifiers {
+                        sticky.press(key, &mut self.event_queue);
+                    }
+                    self.event_queue.push_back(Event::Input(
+                        Input::Button(ButtonArgs {
+                            state: ButtonState::Press,
+                            button: Button::Keyboard(self.map_key(key, scancode)),
+                            scancode: Some(scancode as i32),
+                        }),
+                        stamp,
+                    ));
+                }
+                glfw::WindowEvent::Key(key, scancode, glfw::Action::Repeat, _)
+                    if self.key_repeat =>
+                {
+                    self.event_queue.push_back(Event::Input(
+                        Input::Button(ButtonArgs {
+                            state: ButtonState::Press,
+                            button: Button::Keyboard(self.map_key(key, scancode)),
+                            scancode: Some(scancode),
+                        }),
+                        stamp,
+                    ));
+                }
+                glfw::WindowEvent::Key(key, scancode, glfw::Action::Release, _) => {
+                    let mapped = self.map_key(key, scancode);
+                    self.event_queue.push_back(Event::Input(
+                        Input::Button(ButtonArgs {
+                            state: ButtonState::Release,
+                            button: Button::Keyboard(mapped),
+                            scancode: Some(scancode as i32),
+                        }),
+                        stamp,
+                    ));
+                    if let Some(ref mut sticky) = self.sticky_modifiers {
+                        sticky.release(key, scancode, mapped, &mut self.event_queue);
+                    }
+                }
+                glfw::WindowEvent::MouseButton(button, glfw::Action::Press, _) => {
+                    if button == glfw::MouseButton::Button1 {
+                        if let Some(edges) = self.drag_edges_at(self.window.get_cursor_pos()) {
+                            self.window_drag = Some(WindowDrag::new(&self.window, edges));
+                            continue;
+                        }
+                    }
+                    self.event_queue.push_back(Event::Input(
+                        Input::Button(ButtonArgs {
+                            state: ButtonState::Press,
+                            button: Button::Mouse(glfw_map_mouse(button)),
+                            scancode: None,
+                        }),
+                        stamp,
+                    ));
+                }
+                glfw::WindowEvent::MouseButton(button, glfw::Action::Release, _) => {
+                    if button == glfw::MouseButton::Button1 {
+                        if let Some(drag) = self.window_drag.take() {
+                            if !drag.report_release {
+                                continue;
+                            }
+                        }
+                    }
+                    self.event_queue.push_back(Event::Input(
+                        Input::Button(ButtonArgs {
+                            state: ButtonState::Release,
+                            button: Button::Mouse(glfw_map_mouse(button)),
+                            scancode: None,
+                        }),
+                        stamp,
+                    ));
+                }
+                glfw::WindowEvent::CursorPos(x, y) => {
+                    if let Some(ref drag) = self.window_drag {
+                        drag.update(&mut self.window);
+                        continue;
+                    }
+                    let (x, y) = match self.cursor_confinement {
+                        Some(rect) => {
+                            let (cx, cy) = clamp_to_rect(rect, (x, y));
+                            if (cx, cy) != (x, y) {
+                                self.window.set_cursor_pos(cx, cy);
+                            }
+                            (cx, cy)
+                        }
+                        None => (x, y),
+                    };
+                    if self.last_mouse_pos == Some((x, y)) {
+                        continue;
+                    }
+                    if !self.reports_mouse_motion() {
+                        continue;
+                    }
+                    self.event_queue.push_back(Event::Input(
+                        Input::Move(Motion::MouseCursor([x, y])),
+                        stamp,
+                    ));
                     match self.last_mouse_pos {
-                        Some((lx, ly)) => self
-                            .event_queue
-                            .push_back(Input::Move(Motion::MouseRelative([x - lx, y - ly]))),
-                        None => (),
+                        Some((lx, ly)) if self.emits_mouse_relative() => {
+                            self.event_queue.push_back(Event::Input(
+                                Input::Move(Motion::MouseRelative([x - lx, y - ly])),
+                                stamp,
+                            ))
+                        }
+                        _ => (),
                     }
                     self.last_mouse_pos = Some((x, y));
                 }
                 glfw::WindowEvent::Scroll(x, y) => {
-                    self.event_queue
-                        .push_back(Input::Move(Motion::MouseScroll([x, y])));
+                    let scale = self.scroll_settings.scale;
+                    let (mut x, mut y) = (x * scale, y * scale);
+                    if self.scroll_settings.discrete {
+                        let [rx, ry] = self.scroll_remainder;
+                        let (tx, ty) = (rx + x, ry + y);
+                        x = tx.trunc();
+                        y = ty.trunc();
+                        self.scroll_remainder = [tx - x, ty - y];
+                        if x == 0.0 && y == 0.0 {
+                            continue;
+                        }
+                    }
+                    self.event_queue.push_back(Event::Input(
+                        Input::Move(Motion::MouseScroll([x, y])),
+                        stamp,
+                    ));
                 }
                 glfw::WindowEvent::Size(w, h) => {
-                    let draw_size = self.draw_size();
-                    self.event_queue.push_back(Input::Resize(ResizeArgs {
-                        window_size: [w as f64, h as f64],
-                        draw_size: draw_size.into(),
-                    }));
+                    if self.confine_to_window {
+                        self.cursor_confinement = Some(content_rect(w, h));
+                    }
+                    self.last_resize.window_size = [w as f64, h as f64];
+                    queue_resize(&mut self.event_queue, first_new, self.last_resize, stamp);
+                }
+                glfw::WindowEvent::FramebufferSize(w, h) => {
+                    self.last_resize.draw_size = [w as u32, h as u32];
+                    queue_resize(&mut self.event_queue, first_new, self.last_resize, stamp);
+                }
+                glfw::WindowEvent::ContentScale(x, y) => {
+                    self.event_queue.push_back(Event::Custom(
+                        CONTENT_SCALE,
+                        Arc::new((x, y)),
+                        stamp,
+                    ));
                 }
                 glfw::WindowEvent::Focus(focus) => {
-                    self.event_queue.push_back(Input::Focus(focus));
+                    focus_changed = true;
+                    self.event_queue
+                        .push_back(Event::Input(Input::Focus(focus), stamp));
+                }
+                glfw::WindowEvent::Pos(x, y) => {
+                    self.event_queue.push_back(Event::Custom(
+                        WINDOW_MOVED,
+                        Arc::new(Position { x, y }),
+                        stamp,
+                    ));
+                }
+                glfw::WindowEvent::Iconify(iconified) => {
+                    focus_changed = true;
+                    let state = if iconified {
+                        WindowState::Iconified
+                    } else if self.window.is_maximized() {
+                        WindowState::Maximized
+                    } else {
+                        WindowState::Restored
+                    };
+                    self.event_queue
+                        .push_back(Event::Custom(WINDOW_STATE, Arc::new(state), stamp));
+                }
+                glfw::WindowEvent::Maximize(maximized) => {
+                    let state = if maximized {
+                        WindowState::Maximized
+                    } else {
+                        WindowState::Restored
+                    };
+                    self.event_queue
+                        .push_back(Event::Custom(WINDOW_STATE, Arc::new(state), stamp));
                 }
                 glfw::WindowEvent::CursorEnter(cursor) => {
-                    self.event_queue.push_back(Input::Cursor(cursor));
+                    self.event_queue
+                        .push_back(Event::Input(Input::Cursor(cursor), stamp));
+                    if !self.reports_mouse_motion() {
+                        self.last_mouse_pos = None;
+                        continue;
+                    }
+                    // Report where the cursor entered or left, so relative motion
+                    // after entering is measured from the entry position.
+                    let (x, y) = self.window.get_cursor_pos();
+                    self.event_queue.push_back(Event::Input(
+                        Input::Move(Motion::MouseCursor([x, y])),
+                        stamp,
+                    ));
+                    self.last_mouse_pos = Some((x, y));
+                }
+                glfw::WindowEvent::FileDrop(paths) if self.group_file_drops => {
+                    let (x, y) = self.window.get_cursor_pos();
+                    self.drop_position = Some([x, y]);
+                    self.event_queue.push_back(Event::Custom(
+                        FILE_DROP,
+                        Arc::new(FileDrop {
+                            paths,
+                            position: [x, y],
+                        }),
+                        stamp,
+                    ));
+                }
+                glfw::WindowEvent::FileDrop(files) => {
+                    let (x, y) = self.window.get_cursor_pos();
+                    self.drop_position = Some([x, y]);
+                    for file in files {
+                        self.event_queue
+                            .push_back(Event::Input(Input::FileDrag(FileDrag::Drop(file)), stamp))
+                    }
+                }
+                event => debug!("Unhandled GLFW event: {:?}", event),
+            }
+        }
+
+        for input in self.injected_events.1.try_iter() {
+            self.event_queue.push_back(Event::Input(input, None));
+        }
+        if self.woken.swap(false, Ordering::SeqCst) {
+            self.event_queue
+                .push_back(Event::Custom(WAKE, Arc::new(()), None));
+        }
+
+        if focus_changed || self.window_drag.is_some() != was_dragging {
+            self.update_polling();
+        }
+        if toggle_event_indicator {
+            self.toggle_event_indicator();
+        }
+        if toggle_fullscreen {
+            if self.is_fullscreen() {
+                self.set_windowed();
+            } else {
+                self.set_fullscreen_on(MonitorId::Primary);
+            }
+        }
+        if self.coalesce_mouse_moves {
+            coalesce_mouse_moves(&mut self.event_queue, first_new);
+        }
+        self.reload_config();
+
+        // println!("checking gamepads");
+        let first_controller_event = self.event_queue.len();
+        for (id, present) in self.joystick_events.try_iter() {
+            for j in self.joysticks.iter_mut().filter(|j| j.joystick.id == id) {
+                j.present = present;
+            }
+            if let Some(ref mut ids) = self.stable_controller_ids {
+                if present {
+                    ids.id(&self.glfw, id);
+                } else {
+                    ids.disconnect(id);
+                }
+            }
+        }
+        let paused = self.pause_while_unfocused.controllers && self.is_inactive();
+        if !paused {
+            // Controllers are polled, so their events happen now.
+            let stamp = Some(timestamp(self.glfw.get_time()));
+            for j in self.joysticks.iter_mut() {
+                j.update(
+                    &mut self.event_queue,
+                    self.joystick_deadzone,
+                    self.joystick_axis_epsilon,
+                    stamp,
+                );
+            }
+        }
+        if self.stable_controller_ids.is_some() || !self.controller_remaps.is_empty() {
+            for event in self.event_queue.iter_mut().skip(first_controller_event) {
+                let id = match controller_id(event) {
+                    Some(id) => id,
+                    None => continue,
+                };
+                if let Some(ref mut ids) = self.stable_controller_ids {
+                    if let Some(slot) = JoystickId::from_i32(*id as i32) {
+                        *id = ids.id(&self.glfw, slot);
+                    }
+                }
+                let id = *id;
+                if let Some(remap) = self.controller_remaps.get(&id) {
+                    remap.apply(event);
+                }
+            }
+        }
+        if let Some((start, ref mut recording)) = self.controller_recording {
+            let time = start.elapsed();
+            for event in self.event_queue.iter_mut().skip(first_controller_event) {
+                if controller_id(event).is_some() {
+                    if let Event::Input(ref input, _) = *event {
+                        recording.events.push((time, input.clone()));
+                    }
+                }
+            }
+        }
+        if let Some((start, recording, mut next)) = self.controller_replay.take() {
+            let time = start.elapsed();
+            while let Some(&(at, ref input)) = recording.events.get(next) {
+                if at > time {
+                    break;
+                }
+                self.event_queue
+                    .push_back(Event::Input(input.clone(), None));
+                next += 1;
+            }
+            if next < recording.events.len() {
+                self.controller_replay = Some((start, recording, next));
+            }
+        }
+        if let (Some(settings), false) = (self.controller_cursor, paused) {
+            self.emulate_cursor(settings, first_controller_event);
+        }
+
+        self.deliver_events(first_new, started);
+    }
+
+    /// Queues an event from outside `flush_messages` the same way as translated events.
+    fn queue_event(&mut self, event: Event) {
+        let first_new = self.event_queue.len();
+        self.event_queue.push_back(event);
+        self.deliver_events(first_new, None);
+    }
+
+    /// Filters the events queued since `first_new` and passes them on
+    /// to subscribers, the event log, the metrics and the forwarding channel.
+    fn deliver_events(&mut self, first_new: usize, started: Option<Instant>) {
+        if let Some(ref mut filter) = self.event_filter {
+            let mut new_events = self.event_queue.split_off(first_new);
+            new_events.retain_mut(|event| match *event {
+                Event::Input(ref mut input, _) => {
+                    let keep = filter(input);
+                    if !keep {
+                        debug!("Event filter dropped {:?}", input);
+                    }
+                    keep
+                }
+                _ => true,
+            });
+            self.event_queue.append(&mut new_events);
+        }
+
+        for event in self.event_queue.iter().skip(first_new) {
+            trace!("Event: {:?}", event);
+        }
+
+        // Send copies of new events to subscribers, forgetting those that hung up.
+        if !self.subscribers.is_empty() {
+            let event_queue = &self.event_queue;
+            self.subscribers.retain(|subscriber| {
+                event_queue
+                    .iter()
+                    .skip(first_new)
+                    .all(|event| subscriber.send(event.clone()).is_ok())
+            });
+        }
+
+        if let Some(ref mut indicator) = self.event_indicator {
+            indicator.update(self.event_queue.iter().skip(first_new));
+        }
+
+        if let (Some(metrics), Some(started)) = (self.metrics.as_mut(), started) {
+            metrics.record(
+                self.event_queue.iter().skip(first_new),
+                self.event_queue.len(),
+                started.elapsed(),
+            );
+        }
+
+        // Forward events to the consumer thread, queuing them again if it hung up.
+        if let Some(sender) = self.event_sender.take() {
+            let mut connected = true;
+            while let Some(event) = self.event_queue.pop_front() {
+                if let Err(mpsc::SendError(event)) = sender.send(event) {
+                    self.event_queue.push_front(event);
+                    connected = false;
+                    break;
                 }
-                glfw::WindowEvent::FileDrop(files) => {
-                    for file in files {
-                        self.event_queue
-                            .push_back(Input::FileDrag(FileDrag::Drop(file)))
+            }
+            if connected {
+                self.event_sender = Some(sender);
+            }
+        }
+    }
+
+    fn emulate_cursor(&mut self, settings: ControllerCursor, first_controller_event: usize) {
+        for input in self.event_queue.iter_mut().skip(first_controller_event) {
+            if let Event::Input(Input::Button(ref mut args), _) = *input {
+                if let Button::Controller(button) = args.button {
+                    if button.id != settings.id {
+                        continue;
+                    }
+                    if button.button == settings.left_button {
+                        args.button = Button::Mouse(MouseButton::Left);
+                    } else if button.button == settings.right_button {
+                        args.button = Button::Mouse(MouseButton::Right);
                     }
                 }
-                _ => (),
             }
         }
 
-        // println!("checking gamepads");
-        for j in self.joysticks.iter_mut() {
-            j.update(&mut self.event_queue, self.joystick_deadzone);
+        let now = Instant::now();
+        let dt = match self.last_controller_cursor_update {
+            Some(last) => {
+                let elapsed = now.duration_since(last);
+                elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.0
+            }
+            None => 0.0,
+        };
+        self.last_controller_cursor_update = Some(now);
+
+        let slot = match self.stable_controller_ids {
+            Some(ref ids) => ids.slot(settings.id),
+            None => JoystickId::from_i32(settings.id as i32),
+        };
+        let (mut dx, mut dy) = match self.joysticks.iter().find(|j| Some(j.joystick.id) == slot) {
+            Some(j) => (j.axis(settings.x_axis), j.axis(settings.y_axis)),
+            None => return,
+        };
+        if dx.abs() < self.joystick_deadzone {
+            dx = 0.0;
+        }
+        if dy.abs() < self.joystick_deadzone {
+            dy = 0.0;
         }
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+
+        let (x, y) = match self.last_mouse_pos {
+            Some(pos) => pos,
+            None => self.window.get_cursor_pos(),
+        };
+        let (w, h) = self.window.get_size();
+        let nx = (x + dx * settings.speed * dt).max(0.0).min(w as f64);
+        let ny = (y + dy * settings.speed * dt).max(0.0).min(h as f64);
+        self.event_queue.push_back(Event::Input(
+            Input::Move(Motion::MouseCursor([nx, ny])),
+            None,
+        ));
+        self.event_queue.push_back(Event::Input(
+            Input::Move(Motion::MouseRelative([nx - x, ny - y])),
+            None,
+        ));
+        // The cursor position reported back by GLFW is then ignored as unchanged.
+        self.window.set_cursor_pos(nx, ny);
+        self.last_mouse_pos = Some((nx, ny));
     }
 
     fn wait_event(&mut self) -> Event {
@@ -279,7 +3697,7 @@ impl GlfwWindow {
                 self.flush_messages();
             }
             if let Some(event) = self.event_queue.pop_front() {
-                return Event::Input(event, None);
+                return event;
             }
         }
     }
@@ -291,7 +3709,7 @@ impl GlfwWindow {
             self.glfw.wait_events_timeout(timeout_secs);
             self.flush_messages();
         }
-        self.event_queue.pop_front().map(|x| Event::Input(x, None))
+        self.event_queue.pop_front()
     }
 
     fn poll_event(&mut self) -> Option<Event> {
@@ -299,132 +3717,906 @@ impl GlfwWindow {
             self.glfw.poll_events();
             self.flush_messages();
         }
-        self.event_queue.pop_front().map(|x| Event::Input(x, None))
+        self.event_queue.pop_front()
     }
 
     fn capture_cursor(&mut self, enabled: bool) {
+        // Raw motion is only used while the cursor is disabled,
+        // which makes `MouseRelative` free of pointer acceleration.
+        if self.glfw.supports_raw_motion() {
+            self.window.set_raw_mouse_motion(enabled);
+        }
         if enabled {
             self.window.set_cursor_mode(glfw::CursorMode::Disabled);
         } else {
-            self.window.set_cursor_mode(glfw::CursorMode::Normal);
+            let mode = self.released_cursor_mode();
+            self.window.set_cursor_mode(mode);
             self.last_mouse_pos = None;
         }
     }
 }
 
-impl BuildFromWindowSettings for GlfwWindow {
-    fn build_from_window_settings(settings: &WindowSettings) -> Result<GlfwWindow, Box<dyn Error>> {
-        GlfwWindow::new(settings)
-    }
+/// Receives copies of the events of a window.
+///
+/// Created by `GlfwWindow::subscribe`.
+pub struct Subscription {
+    receiver: mpsc::Receiver<Event>,
+    window_id: WindowId,
+}
+
+impl Subscription {
+    /// Returns the id of the window the events come from.
+    pub fn window_id(&self) -> WindowId {
+        self.window_id
+    }
+
+    /// Returns the next event, if any.
+    pub fn try_recv(&self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Returns an iterator over the events received so far.
+    pub fn try_iter(&self) -> mpsc::TryIter<'_, Event> {
+        self.receiver.try_iter()
+    }
+}
+
+/// A stream of the events of a window.
+///
+/// Created by `GlfwWindow::event_stream`.
+/// Each poll waits for events on the calling thread for up to the timeout,
+/// so the stream must be polled on the main thread.
+/// The stream ends when the window should close and all events are handled.
+#[cfg(feature = "async")]
+pub struct EventStream<'a> {
+    window: &'a mut GlfwWindow,
+    timeout: Duration,
+}
+
+#[cfg(feature = "async")]
+impl<'a> EventStream<'a> {
+    /// Sets how long a poll waits for events, 1 millisecond by default.
+    ///
+    /// Longer timeouts use less CPU while idle, but block other tasks longer.
+    pub fn timeout(mut self, timeout: Duration) -> EventStream<'a> {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a> futures_core::Stream for EventStream<'a> {
+    type Item = Event;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Event>> {
+        let this = self.get_mut();
+        if let Some(event) = this.window.wait_event_timeout(this.timeout) {
+            return std::task::Poll::Ready(Some(event));
+        }
+        if this.window.should_close() {
+            return std::task::Poll::Ready(None);
+        }
+        // GLFW can not notify the executor, so ask to be polled again.
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    }
+}
+
+/// Sends input events to a window from any thread.
+///
+/// Created by `GlfwWindow::event_sender`.
+/// Sending an event wakes up a window waiting for events.
+#[derive(Clone)]
+pub struct EventSender {
+    sender: mpsc::Sender<Input>,
+}
+
+impl EventSender {
+    /// Queues an input event, returning `false` if the window is gone.
+    pub fn send(&self, input: Input) -> bool {
+        if self.sender.send(input).is_err() {
+            return false;
+        }
+        post_empty_event();
+        true
+    }
+}
+
+/// Wakes up a window waiting for events from any thread.
+///
+/// Created by `GlfwWindow::waker`.
+/// Use this to let the main loop handle the results of background work promptly.
+#[derive(Clone, Debug)]
+pub struct Waker {
+    woken: Arc<AtomicBool>,
+}
+
+impl Waker {
+    /// Queues a `WAKE` event, which ends a blocking `wait_event` or `wait_event_timeout`.
+    ///
+    /// Several calls before the window handles events queue a single event.
+    pub fn wake(&self) {
+        self.woken.store(true, Ordering::SeqCst);
+        post_empty_event();
+    }
+}
+
+/// Wakes up GLFW if it is waiting for events.
+fn post_empty_event() {
+    // Posting an empty event is allowed from any thread,
+    // and GLFW ignores it when not initialized.
+    unsafe { glfw::ffi::glfwPostEmptyEvent() };
+}
+
+#[cfg(feature = "raw-window-handle")]
+impl raw_window_handle::HasWindowHandle for GlfwWindow {
+    fn window_handle(
+        &self,
+    ) -> Result<raw_window_handle::WindowHandle<'_>, raw_window_handle::HandleError> {
+        self.window.window_handle()
+    }
+}
+
+#[cfg(feature = "raw-window-handle")]
+impl raw_window_handle::HasDisplayHandle for GlfwWindow {
+    fn display_handle(
+        &self,
+    ) -> Result<raw_window_handle::DisplayHandle<'_>, raw_window_handle::HandleError> {
+        self.window.display_handle()
+    }
+}
+
+impl BuildFromWindowSettings for GlfwWindow {
+    fn build_from_window_settings(settings: &WindowSettings) -> Result<GlfwWindow, Box<dyn Error>> {
+        GlfwWindow::new(settings)
+    }
+}
+
+impl Window for GlfwWindow {
+    fn size(&self) -> Size {
+        let (w, h) = self.window.get_size();
+        Size {
+            width: w as f64,
+            height: h as f64,
+        }
+    }
+
+    fn draw_size(&self) -> Size {
+        let (w, h) = self.window.get_framebuffer_size();
+        Size {
+            width: w as f64,
+            height: h as f64,
+        }
+    }
+
+    fn set_should_close(&mut self, value: bool) {
+        self.window.set_should_close(value);
+    }
+
+    fn should_close(&self) -> bool {
+        self.window.should_close()
+    }
+
+    fn swap_buffers(&mut self) {
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            self.save_screenshot();
+        }
+        // Vulkan presents frames through its own swap chain.
+        if !self.opengl {
+            return;
+        }
+        if let Some(ref mut reader) = self.frame_reader {
+            reader.read(self.window.get_framebuffer_size());
+        }
+        self.window.swap_buffers();
+        if let Some(ref mut counter) = self.fps_in_title {
+            if let Some((fps, frame_time)) = counter.frame() {
+                self.window.set_title(&format!(
+                    "{} - {:.0} FPS ({:.2} ms)",
+                    self.title,
+                    fps,
+                    frame_time * 1000.0
+                ));
+            }
+        }
+    }
+
+    fn wait_event(&mut self) -> Event {
+        self.wait_event()
+    }
+
+    fn wait_event_timeout(&mut self, timeout: Duration) -> Option<Event> {
+        self.wait_event_timeout(timeout)
+    }
+
+    fn poll_event(&mut self) -> Option<Event> {
+        self.poll_event()
+    }
+}
+
+impl AdvancedWindow for GlfwWindow {
+    fn get_title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn set_title(&mut self, value: String) {
+        self.window.set_title(&value);
+        self.title = value;
+    }
+
+    fn get_automatic_close(&self) -> bool {
+        self.automatic_close
+    }
+
+    fn set_automatic_close(&mut self, value: bool) {
+        self.automatic_close = value;
+    }
+
+    fn get_exit_on_esc(&self) -> bool {
+        self.exit_shortcut == Some(ESCAPE)
+    }
+
+    fn set_exit_on_esc(&mut self, value: bool) {
+        self.exit_shortcut = if value { Some(ESCAPE) } else { None };
+    }
+
+    fn set_capture_cursor(&mut self, value: bool) {
+        self.capture_cursor(value)
+    }
+
+    fn show(&mut self) {
+        self.window.show();
+    }
+
+    fn hide(&mut self) {
+        self.window.hide();
+    }
+
+    fn get_position(&self) -> Option<Position> {
+        let (x, y) = self.window.get_pos();
+        Some(Position { x: x, y: y })
+    }
+
+    fn set_position<P: Into<Position>>(&mut self, pos: P) {
+        let pos: Position = pos.into();
+        self.window.set_pos(pos.x, pos.y);
+    }
+
+    fn set_size<S: Into<Size>>(&mut self, size: S) {
+        let size: Size = size.into();
+        self.window.set_size(size.width as i32, size.height as i32);
+    }
+}
+
+impl OpenGLWindow for GlfwWindow {
+    fn get_proc_address(&mut self, proc_name: &str) -> ProcAddress {
+        self.window.get_proc_address(proc_name) as *const _
+    }
+
+    fn is_current(&self) -> bool {
+        self.window.is_current()
+    }
+
+    fn make_current(&mut self) {
+        if self.opengl {
+            self.window.make_current()
+        }
+    }
+}
+
+/// Returns the supported video mode closest to the requested one.
+///
+/// Prefers the same resolution with the closest refresh rate.
+fn closest_video_mode(modes: &[VideoMode], wanted: VideoMode) -> Option<VideoMode> {
+    let diff = |a: u32, b: u32| (a as i64 - b as i64).abs();
+    modes.iter().cloned().min_by_key(|mode| {
+        (
+            diff(mode.width, wanted.width) + diff(mode.height, wanted.height),
+            diff(mode.refresh_rate, wanted.refresh_rate),
+        )
+    })
+}
+
+/// Returns the current window and framebuffer sizes.
+fn resize_args(window: &glfw::Window) -> ResizeArgs {
+    let (w, h) = window.get_size();
+    let (dw, dh) = window.get_framebuffer_size();
+    ResizeArgs {
+        window_size: [w as f64, h as f64],
+        draw_size: [dw as u32, dh as u32],
+    }
+}
+
+/// Queues a resize event, merging it with a resize event queued just before.
+///
+/// Window and framebuffer size events are reported separately by GLFW,
+/// and during interactive resizing in large numbers.
+fn queue_resize(
+    queue: &mut VecDeque<Event>,
+    first_new: usize,
+    args: ResizeArgs,
+    stamp: Option<TimeStamp>,
+) {
+    if queue.len() > first_new {
+        if let Some(&mut Event::Input(Input::Resize(ref mut last), ref mut last_stamp)) =
+            queue.back_mut()
+        {
+            *last = args;
+            *last_stamp = stamp;
+            return;
+        }
+    }
+    queue.push_back(Event::Input(Input::Resize(args), stamp));
+}
+
+/// Enables polling for the GLFW events that are translated.
+fn set_polling(window: &mut glfw::Window, text_input: bool, cursor_pos: bool) {
+    window.set_key_polling(true);
+    window.set_mouse_button_polling(true);
+    window.set_scroll_polling(true);
+    window.set_cursor_enter_polling(true);
+    window.set_close_polling(true);
+    window.set_size_polling(true);
+    window.set_framebuffer_size_polling(true);
+    window.set_content_scale_polling(true);
+    window.set_pos_polling(true);
+    window.set_focus_polling(true);
+    window.set_iconify_polling(true);
+    window.set_maximize_polling(true);
+    window.set_drag_and_drop_polling(true);
+    window.set_char_polling(text_input);
+    window.set_char_mods_polling(text_input);
+    window.set_cursor_pos_polling(cursor_pos);
+    window.set_refresh_polling(false);
+}
+
+/// Converts a GLFW time in seconds to a time stamp in milliseconds.
+fn timestamp(time: f64) -> TimeStamp {
+    (time * 1000.0) as TimeStamp
+}
+
+/// Returns the cursor position that relative motion is measured from.
+///
+/// The cursor moves unseen while mouse motion is not reported,
+/// so relative motion starts over from the next reported position.
+fn tracked_mouse_pos(last: Option<(f64, f64)>, reports_motion: bool) -> Option<(f64, f64)> {
+    if reports_motion {
+        last
+    } else {
+        None
+    }
+}
+
+/// Clamps a position to a rectangle `[x, y, width, height]`.
+fn clamp_to_rect(rect: [f64; 4], (x, y): (f64, f64)) -> (f64, f64) {
+    let [rx, ry, rw, rh] = rect;
+    (x.max(rx).min(rx + rw), y.max(ry).min(ry + rh))
+}
+
+/// Merges runs of mouse moves in the queue, starting at an index.
+fn coalesce_mouse_moves(queue: &mut VecDeque<Event>, start: usize) {
+    // The merged motion and the time stamp of the last merged event.
+    type Merged = Option<([f64; 2], Option<TimeStamp>)>;
+
+    fn flush(queue: &mut VecDeque<Event>, cursor: &mut Merged, relative: &mut Merged) {
+        if let Some((pos, stamp)) = cursor.take() {
+            queue.push_back(Event::Input(Input::Move(Motion::MouseCursor(pos)), stamp));
+        }
+        if let Some((delta, stamp)) = relative.take() {
+            queue.push_back(Event::Input(
+                Input::Move(Motion::MouseRelative(delta)),
+                stamp,
+            ));
+        }
+    }
+
+    let events: Vec<Event> = queue.drain(start..).collect();
+    let mut cursor: Merged = None;
+    let mut relative: Merged = None;
+    for event in events {
+        match event {
+            Event::Input(Input::Move(Motion::MouseCursor(pos)), stamp) => {
+                cursor = Some((pos, stamp))
+            }
+            Event::Input(Input::Move(Motion::MouseRelative([dx, dy])), stamp) => {
+                let [x, y] = relative.map_or([0.0, 0.0], |(delta, _)| delta);
+                relative = Some(([x + dx, y + dy], stamp));
+            }
+            event => {
+                flush(queue, &mut cursor, &mut relative);
+                queue.push_back(event);
+            }
+        }
+    }
+    flush(queue, &mut cursor, &mut relative);
+}
+
+/// Returns the modifier of a modifier key.
+fn key_modifier(key: glfw::Key) -> Option<glfw::Modifiers> {
+    match key {
+        glfw::Key::LeftShift | glfw::Key::RightShift => Some(glfw::Modifiers::Shift),
+        glfw::Key::LeftControl | glfw::Key::RightControl => Some(glfw::Modifiers::Control),
+        glfw::Key::LeftAlt | glfw::Key::RightAlt => Some(glfw::Modifiers::Alt),
+        glfw::Key::LeftSuper | glfw::Key::RightSuper => Some(glfw::Modifiers::Super),
+        _ => None,
+    }
+}
+
+/// Returns the rectangle of positions inside a content area of a size.
+fn content_rect(w: i32, h: i32) -> [f64; 4] {
+    [0.0, 0.0, (w - 1).max(0) as f64, (h - 1).max(0) as f64]
+}
+
+/// Packs RGBA bytes into the pixel format used by GLFW images.
+fn pack_rgba(pixels: &[u8]) -> Vec<u32> {
+    pixels
+        .chunks(4)
+        .map(|p| u32::from_ne_bytes([p[0], p[1], p[2], p[3]]))
+        .collect()
+}
+
+/// Reverses the order of rows in RGBA pixels.
+fn flip_rows(pixels: &[u8], width: u32) -> Vec<u8> {
+    let row = (width as usize * 4).max(1);
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for line in pixels.chunks(row).rev() {
+        flipped.extend_from_slice(line);
+    }
+    flipped
+}
+
+/// Reads frames back asynchronously with two pixel buffer objects.
+struct FrameReader {
+    callback: Box<dyn FnMut(&FrameCapture)>,
+    buffers: [gl::types::GLuint; 2],
+    // Size of the frame each buffer is reading, if any.
+    sizes: [Option<(i32, i32)>; 2],
+    next: usize,
+}
+
+impl FrameReader {
+    fn new(callback: Box<dyn FnMut(&FrameCapture)>) -> FrameReader {
+        let mut buffers = [0; 2];
+        unsafe {
+            gl::GenBuffers(2, buffers.as_mut_ptr());
+        }
+        FrameReader {
+            callback,
+            buffers,
+            sizes: [None; 2],
+            next: 0,
+        }
+    }
+
+    /// Starts reading the back buffer and delivers the previously read frame.
+    fn read(&mut self, (w, h): (i32, i32)) {
+        let i = self.next;
+        unsafe {
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, self.buffers[i]);
+            gl::BufferData(
+                gl::PIXEL_PACK_BUFFER,
+                (w.max(0) as isize) * (h.max(0) as isize) * 4,
+                ptr::null(),
+                gl::STREAM_READ,
+            );
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(0, 0, w, h, gl::RGBA, gl::UNSIGNED_BYTE, ptr::null_mut());
+        }
+        self.sizes[i] = Some((w, h));
+        self.next = 1 - i;
+
+        let mut frame = None;
+        if let Some((w, h)) = self.sizes[self.next].take() {
+            let (width, height) = (w.max(0) as u32, h.max(0) as u32);
+            unsafe {
+                gl::BindBuffer(gl::PIXEL_PACK_BUFFER, self.buffers[self.next]);
+                let data = gl::MapBuffer(gl::PIXEL_PACK_BUFFER, gl::READ_ONLY) as *const u8;
+                if !data.is_null() {
+                    let len = width as usize * height as usize * 4;
+                    frame = Some(FrameCapture {
+                        width,
+                        height,
+                        pixels: flip_rows(slice::from_raw_parts(data, len), width),
+                    });
+                    gl::UnmapBuffer(gl::PIXEL_PACK_BUFFER);
+                }
+            }
+        }
+        unsafe {
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+        }
+        if let Some(frame) = frame {
+            (self.callback)(&frame);
+        }
+    }
+
+    /// Deletes the buffers, which requires the OpenGL context to be current.
+    fn delete(self) {
+        unsafe {
+            gl::DeleteBuffers(2, self.buffers.as_ptr());
+        }
+    }
+}
+
+/// The edges of a window that are being dragged.
+///
+/// No edges means the whole window is moved.
+#[derive(Copy, Clone, Debug, Default)]
+struct DragEdges {
+    left: bool,
+    right: bool,
+    top: bool,
+    bottom: bool,
+}
+
+/// Moves or resizes a window following the cursor.
+struct WindowDrag {
+    edges: DragEdges,
+    // Cursor position in screen coordinates.
+    start_cursor: (f64, f64),
+    start_pos: (i32, i32),
+    start_size: (i32, i32),
+    // Whether the button release ending the drag is reported.
+    report_release: bool,
+}
+
+impl WindowDrag {
+    fn new(window: &glfw::Window, edges: DragEdges) -> WindowDrag {
+        WindowDrag {
+            edges,
+            start_cursor: WindowDrag::screen_cursor(window),
+            start_pos: window.get_pos(),
+            start_size: window.get_size(),
+            report_release: false,
+        }
+    }
+
+    fn screen_cursor(window: &glfw::Window) -> (f64, f64) {
+        let (wx, wy) = window.get_pos();
+        let (x, y) = window.get_cursor_pos();
+        (wx as f64 + x, wy as f64 + y)
+    }
+
+    fn update(&self, window: &mut glfw::Window) {
+        let (cx, cy) = WindowDrag::screen_cursor(window);
+        let dx = (cx - self.start_cursor.0) as i32;
+        let dy = (cy - self.start_cursor.1) as i32;
+        let DragEdges {
+            left,
+            right,
+            top,
+            bottom,
+        } = self.edges;
+        if !(left || right || top || bottom) {
+            window.set_pos(self.start_pos.0 + dx, self.start_pos.1 + dy);
+            return;
+        }
+
+        let (mut x, mut y) = self.start_pos;
+        let (mut w, mut h) = self.start_size;
+        if left {
+            let dx = dx.min(w - 1);
+            x += dx;
+            w -= dx;
+        }
+        if right {
+            w = (w + dx).max(1);
+        }
+        if top {
+            let dy = dy.min(h - 1);
+            y += dy;
+            h -= dy;
+        }
+        if bottom {
+            h = (h + dy).max(1);
+        }
+        window.set_pos(x, y);
+        window.set_size(w, h);
+    }
+}
+
+/// Watches a configuration file for changes.
+struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Option<Instant>,
+    values: HashMap<String, String>,
 }
 
-impl Window for GlfwWindow {
-    fn size(&self) -> Size {
-        let (w, h) = self.window.get_size();
-        Size {
-            width: w as f64,
-            height: h as f64,
+impl ConfigWatcher {
+    fn new(path: PathBuf) -> ConfigWatcher {
+        ConfigWatcher {
+            path,
+            modified: None,
+            last_check: None,
+            values: HashMap::new(),
         }
     }
 
-    fn draw_size(&self) -> Size {
-        let (w, h) = self.window.get_framebuffer_size();
-        Size {
-            width: w as f64,
-            height: h as f64,
+    /// Returns the keys and values that changed since the last check.
+    fn changes(&mut self) -> Vec<(String, String)> {
+        let now = Instant::now();
+        if let Some(last_check) = self.last_check {
+            if now.duration_since(last_check) < Duration::from_millis(500) {
+                return vec![];
+            }
         }
-    }
+        self.last_check = Some(now);
 
-    fn set_should_close(&mut self, value: bool) {
-        self.window.set_should_close(value);
-    }
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == self.modified {
+            return vec![];
+        }
+        self.modified = modified;
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(_) => return vec![],
+        };
 
-    fn should_close(&self) -> bool {
-        self.window.should_close()
+        let mut changes = vec![];
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key.trim().to_string(), value.trim().to_string()),
+                _ => continue,
+            };
+            if self.values.get(&key) != Some(&value) {
+                self.values.insert(key.clone(), value.clone());
+                changes.push((key, value));
+            }
+        }
+        changes
     }
+}
 
-    fn swap_buffers(&mut self) {
-        self.window.swap_buffers()
-    }
+/// Keeps a log of events and shows the latest one in the title bar of a secondary window.
+struct EventIndicator {
+    hotkey: keyboard::Key,
+    log: VecDeque<String>,
+    window: Option<(glfw::PWindow, Receiver<(f64, glfw::WindowEvent)>)>,
+}
 
-    fn wait_event(&mut self) -> Event {
-        self.wait_event()
-    }
+impl EventIndicator {
+    const CAPACITY: usize = 256;
 
-    fn wait_event_timeout(&mut self, timeout: Duration) -> Option<Event> {
-        self.wait_event_timeout(timeout)
+    fn new(hotkey: keyboard::Key) -> EventIndicator {
+        EventIndicator {
+            hotkey,
+            log: VecDeque::new(),
+            window: None,
+        }
     }
 
-    fn poll_event(&mut self) -> Option<Event> {
-        self.poll_event()
+    fn update<'a, I: Iterator<Item = &'a Event>>(&mut self, events: I) {
+        for event in events {
+            if self.log.len() == EventIndicator::CAPACITY {
+                self.log.pop_front();
+            }
+            self.log.push_back(format!("{:?}", event));
+        }
+
+        let mut closed = false;
+        if let Some((ref mut window, ref events)) = self.window {
+            for (_, event) in glfw::flush_messages(events) {
+                if let glfw::WindowEvent::Close = event {
+                    closed = true;
+                }
+            }
+            let last = self.log.back().map(|s| &s[..]).unwrap_or("");
+            window.set_title(&format!("Event log ({}): {}", self.log.len(), last));
+        }
+        if closed {
+            self.window = None;
+        }
     }
 }
 
-impl AdvancedWindow for GlfwWindow {
-    fn get_title(&self) -> String {
-        self.title.clone()
-    }
+/// Counts frames for showing the frame rate in the title.
+struct FpsCounter {
+    interval: Duration,
+    frames: u32,
+    since: Instant,
+}
 
-    fn set_title(&mut self, value: String) {
-        self.window.set_title(&value)
+impl FpsCounter {
+    fn new(interval: Duration) -> FpsCounter {
+        FpsCounter {
+            interval,
+            frames: 0,
+            since: Instant::now(),
+        }
     }
 
-    fn get_automatic_close(&self) -> bool {
-        self.automatic_close
+    /// Counts a frame and returns frames per second and seconds per frame
+    /// when the interval has passed.
+    fn frame(&mut self) -> Option<(f64, f64)> {
+        self.frames += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed < self.interval {
+            return None;
+        }
+        let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.0;
+        let frames = self.frames as f64;
+        self.frames = 0;
+        self.since = Instant::now();
+        Some((frames / secs, secs / frames))
     }
+}
 
-    fn set_automatic_close(&mut self, value: bool) {
-        self.automatic_close = value;
-    }
+/// Collects event statistics.
+struct MetricsCollector {
+    metrics: EventMetrics,
+    // Events counted in the current second.
+    counts: HashMap<&'static str, u32>,
+    since: Instant,
+}
 
-    fn get_exit_on_esc(&self) -> bool {
-        self.exit_on_esc
+impl MetricsCollector {
+    fn new() -> MetricsCollector {
+        MetricsCollector {
+            metrics: EventMetrics::default(),
+            counts: HashMap::new(),
+            since: Instant::now(),
+        }
     }
 
-    fn set_exit_on_esc(&mut self, value: bool) {
-        self.exit_on_esc = value
+    fn record<'a, I>(&mut self, new_events: I, queue_len: usize, duration: Duration)
+    where
+        I: Iterator<Item = &'a Event>,
+    {
+        if self.since.elapsed() >= Duration::from_secs(1) {
+            self.metrics.events_per_second = self.counts.drain().collect();
+            self.since = Instant::now();
+        }
+        for event in new_events {
+            *self.counts.entry(event_kind(event)).or_insert(0) += 1;
+        }
+        self.metrics.queue_high_water_mark = self.metrics.queue_high_water_mark.max(queue_len);
+        self.metrics.last_poll_duration = duration;
+        self.metrics.max_poll_duration = self.metrics.max_poll_duration.max(duration);
     }
+}
 
-    fn set_capture_cursor(&mut self, value: bool) {
-        self.capture_cursor(value)
+/// Returns the name of the kind of an event.
+fn event_kind(event: &Event) -> &'static str {
+    match *event {
+        Event::Input(ref input, _) => match *input {
+            Input::Button(ButtonArgs { button, .. }) => match button {
+                Button::Keyboard(_) => "keyboard",
+                Button::Mouse(_) => "mouse_button",
+                Button::Controller(_) => "controller_button",
+                Button::Hat(_) => "controller_hat",
+            },
+            Input::Move(motion) => match motion {
+                Motion::MouseCursor(_) => "mouse_cursor",
+                Motion::MouseRelative(_) => "mouse_relative",
+                Motion::MouseScroll(_) => "mouse_scroll",
+                Motion::ControllerAxis(_) => "controller_axis",
+                Motion::Touch(_) => "touch",
+            },
+            Input::Text(_) => "text",
+            Input::Resize(_) => "resize",
+            Input::Focus(_) => "focus",
+            Input::Cursor(_) => "cursor",
+            Input::FileDrag(_) => "file_drag",
+            Input::Close(_) => "close",
+        },
+        Event::Loop(_) => "loop",
+        Event::Custom(EventId(id), _, _) => id,
     }
+}
 
-    fn show(&mut self) {
-        self.window.show();
-    }
+/// Latches tapped modifier keys for the next key press.
+struct StickyModifiers {
+    /// Modifier that is pressed without any other key so far.
+    tap: Option<glfw::Key>,
+    /// Modifiers waiting for the next key press, with their scancodes and mapped keys.
+    latched: Vec<(glfw::Key, glfw::Scancode, keyboard::Key)>,
+    /// Modifiers held down by the synthesized events, and the key that holds them.
+    held: Vec<(glfw::Key, glfw::Scancode, keyboard::Key)>,
+    held_by: Option<glfw::Key>,
+}
 
-    fn hide(&mut self) {
-        self.window.hide();
+impl StickyModifiers {
+    fn new() -> StickyModifiers {
+        StickyModifiers {
+            tap: None,
+            latched: Vec::new(),
+            held: Vec::new(),
+            held_by: None,
+        }
     }
 
-    fn get_position(&self) -> Option<Position> {
-        let (x, y) = self.window.get_pos();
-        Some(Position { x: x, y: y })
+    fn is_modifier(key: glfw::Key) -> bool {
+        key_modifier(key).is_some()
     }
 
-    fn set_position<P: Into<Position>>(&mut self, pos: P) {
-        let pos: Position = pos.into();
-        self.window.set_pos(pos.x, pos.y);
+    /// Called before a key press is queued.
+    fn press(&mut self, key: glfw::Key, event_queue: &mut VecDeque<Event>) {
+        if StickyModifiers::is_modifier(key) {
+            self.tap = Some(key);
+            return;
+        }
+        self.tap = None;
+        if self.held_by.is_some() || self.latched.is_empty() {
+            return;
+        }
+        for &(_, scancode, mapped) in &self.latched {
+            event_queue.push_back(Event::Input(
+                Input::Button(ButtonArgs {
+                    state: ButtonState::Press,
+                    button: Button::Keyboard(mapped),
+                    scancode: Some(scancode),
+                }),
+                None,
+            ));
+        }
+        self.held = self.latched.drain(..).collect();
+        self.held_by = Some(key);
     }
 
-    fn set_size<S: Into<Size>>(&mut self, size: S) {
-        let size: Size = size.into();
-        self.window.set_size(size.width as i32, size.height as i32);
+    /// Called after a key release is queued, with the key mapped like in the release event.
+    fn release(
+        &mut self,
+        key: glfw::Key,
+        scancode: glfw::Scancode,
+        mapped: keyboard::Key,
+        event_queue: &mut VecDeque<Event>,
+    ) {
+        if StickyModifiers::is_modifier(key) {
+            if self.tap == Some(key) {
+                self.tap = None;
+                match self.latched.iter().position(|&(m, _, _)| m == key) {
+                    Some(i) => {
+                        self.latched.remove(i);
+                    }
+                    None => self.latched.push((key, scancode, mapped)),
+                }
+            }
+            return;
+        }
+        if self.held_by != Some(key) {
+            return;
+        }
+        for (_, scancode, mapped) in self.held.drain(..) {
+            event_queue.push_back(Event::Input(
+                Input::Button(ButtonArgs {
+                    state: ButtonState::Release,
+                    button: Button::Keyboard(mapped),
+                    scancode: Some(scancode),
+                }),
+                None,
+            ));
+        }
+        self.held_by = None;
     }
 }
 
-impl OpenGLWindow for GlfwWindow {
-    fn get_proc_address(&mut self, proc_name: &str) -> ProcAddress {
-        self.window.get_proc_address(proc_name) as *const _
-    }
-
-    fn is_current(&self) -> bool {
-        self.window.is_current()
-    }
-
-    fn make_current(&mut self) {
-        self.window.make_current()
+/// Maps a key using the current keyboard layout for printable keys.
+///
+/// GLFW names keys by their US layout position, so for example
+/// the key reported as `Y` types "z" on a German keyboard.
+fn glfw_map_key_in_layout(keycode: glfw::Key, scancode: glfw::Scancode) -> keyboard::Key {
+    let keypad = glfw::Key::Kp0 as i32..=glfw::Key::KpEqual as i32;
+    if !keypad.contains(&(keycode as i32)) {
+        if let Some(name) = glfw::get_key_name(Some(keycode), Some(scancode)) {
+            let mut chars = name.chars();
+            if let (Some(ch), None) = (chars.next(), chars.next()) {
+                if ch.is_ascii_graphic() {
+                    let key = keyboard::Key::from(ch.to_ascii_lowercase() as u32);
+                    if key != keyboard::Key::Unknown {
+                        return key;
+                    }
+                }
+            }
+        }
     }
+    glfw_map_key(keycode)
 }
 
 fn glfw_map_key(keycode: glfw::Key) -> keyboard::Key {
@@ -467,7 +4659,7 @@ fn glfw_map_key(keycode: glfw::Key) -> keyboard::Key {
         glfw::Key::X => Key::X,
         glfw::Key::Y => Key::Y,
         glfw::Key::Z => Key::Z,
-        glfw::Key::Apostrophe => Key::Unknown,
+        glfw::Key::Apostrophe => Key::Quote,
         glfw::Key::Backslash => Key::Backslash,
         glfw::Key::Backspace => Key::Backspace,
         glfw::Key::CapsLock => Key::CapsLock,
@@ -570,70 +4762,235 @@ fn glfw_map_mouse(mouse_button: glfw::MouseButton) -> MouseButton {
     }
 }
 
+/// Returns the controller id of a controller event.
+fn controller_id(event: &mut Event) -> Option<&mut u32> {
+    match *event {
+        Event::Input(Input::Move(Motion::ControllerAxis(ref mut args)), _) => Some(&mut args.id),
+        Event::Input(
+            Input::Button(ButtonArgs {
+                button: Button::Controller(ref mut button),
+                ..
+            }),
+            _,
+        ) => Some(&mut button.id),
+        _ => None,
+    }
+}
+
+/// Assigns controllers ids that stay the same when they reconnect.
+struct StableControllerIds {
+    // The GUID, and the slot while connected, by id.
+    controllers: Vec<(Option<String>, Option<JoystickId>)>,
+}
+
+impl StableControllerIds {
+    /// Returns the id of the controller in a slot, assigning one if needed.
+    fn id(&mut self, glfw: &glfw::Glfw, slot: JoystickId) -> u32 {
+        if let Some(id) = self.controllers.iter().position(|c| c.1 == Some(slot)) {
+            return id as u32;
+        }
+        let guid = glfw.get_joystick(slot).get_guid();
+        let id = match self
+            .controllers
+            .iter()
+            .position(|c| c.1.is_none() && c.0 == guid)
+        {
+            Some(id) => id,
+            None => {
+                self.controllers.push((guid, None));
+                self.controllers.len() - 1
+            }
+        };
+        self.controllers[id].1 = Some(slot);
+        id as u32
+    }
+
+    /// Returns the id of the controller in a slot, if it has one.
+    fn get(&self, slot: JoystickId) -> Option<u32> {
+        self.controllers
+            .iter()
+            .position(|c| c.1 == Some(slot))
+            .map(|id| id as u32)
+    }
+
+    fn disconnect(&mut self, slot: JoystickId) {
+        for c in &mut self.controllers {
+            if c.1 == Some(slot) {
+                c.1 = None;
+            }
+        }
+    }
+
+    /// Returns the slot of a connected controller.
+    fn slot(&self, id: u32) -> Option<JoystickId> {
+        self.controllers.get(id as usize).and_then(|c| c.1)
+    }
+}
+
+/// Sets the GLFW error callback to report errors.
+///
+/// Every window gets its own receiver, and receivers of closed windows are forgotten.
+/// This replaces any error callback set before.
+fn watch_errors(glfw: &mut glfw::Glfw) -> mpsc::Receiver<GlfwError> {
+    let (sender, receiver) = mpsc::channel();
+    ERROR_WATCHERS.with(|watchers| watchers.borrow_mut().push(sender));
+    glfw.set_error_callback(|error, description| {
+        ERROR_WATCHERS.with(|watchers| {
+            watchers.borrow_mut().retain(|sender| {
+                sender
+                    .send(GlfwError {
+                        error,
+                        description: description.clone(),
+                    })
+                    .is_ok()
+            })
+        });
+    });
+    receiver
+}
+
+thread_local! {
+    // The windows watching joysticks, since GLFW has a single joystick callback.
+    static JOYSTICK_WATCHERS: RefCell<Vec<mpsc::Sender<(JoystickId, bool)>>> =
+        const { RefCell::new(Vec::new()) };
+    // The windows watching errors, since GLFW has a single error callback.
+    static ERROR_WATCHERS: RefCell<Vec<mpsc::Sender<GlfwError>>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Sets the GLFW joystick callback to report connections and disconnections.
+///
+/// Every window gets its own receiver, and receivers of closed windows are forgotten.
+/// This replaces any joystick callback set before.
+fn watch_joysticks(glfw: &mut glfw::Glfw) -> mpsc::Receiver<(JoystickId, bool)> {
+    let (sender, receiver) = mpsc::channel();
+    JOYSTICK_WATCHERS.with(|watchers| watchers.borrow_mut().push(sender));
+    glfw.set_joystick_callback(|id, event| {
+        let connected = event == glfw::JoystickEvent::Connected;
+        JOYSTICK_WATCHERS.with(|watchers| {
+            watchers
+                .borrow_mut()
+                .retain(|sender| sender.send((id, connected)).is_ok())
+        });
+    });
+    receiver
+}
+
 /// helper struct for joystick
 struct JoystickHelper {
     /// joystick to check
     joystick: Joystick,
 
-    // states
-    buttons: HashMap<u8, bool>,
-    axes: HashMap<u8, f64>,
+    // states, indexed by axis and button
+    buttons: Vec<bool>,
+    axes: Vec<f64>,
+    /// whether the joystick is present, as reported by GLFW
+    present: bool,
     /// last known connected state
     connected: bool,
+    /// use the standard gamepad layout when available
+    gamepad: bool,
+    /// report gamepad triggers from 0 to 1
+    normalize_triggers: bool,
 }
 impl JoystickHelper {
     fn new(joystick: Joystick) -> Self {
         Self {
+            present: joystick.is_present(),
             joystick,
             connected: false,
-            buttons: HashMap::new(),
-            axes: HashMap::new(),
+            buttons: Vec::new(),
+            axes: Vec::new(),
+            gamepad: false,
+            normalize_triggers: false,
+        }
+    }
+
+    fn set_normalize_triggers(&mut self, normalize_triggers: bool) {
+        if self.normalize_triggers != normalize_triggers {
+            self.normalize_triggers = normalize_triggers;
+            // read the new range as if just connected
+            self.connected = false;
+        }
+    }
+
+    fn set_gamepad(&mut self, gamepad: bool) {
+        if self.gamepad != gamepad {
+            self.gamepad = gamepad;
+            // read the new layout as if just connected
+            self.connected = false;
+        }
+    }
+
+    /// Reads the axes and buttons, using the standard gamepad layout if enabled.
+    fn read(&self) -> JoystickState<'_> {
+        JoystickState::read(&self.joystick, self.gamepad, self.normalize_triggers)
+    }
+
+    /// Returns the current position of an axis, or zero if not connected.
+    fn axis(&self, axis: u8) -> f64 {
+        if !self.connected {
+            return 0.0;
         }
+        self.read().axis(axis as usize)
     }
 
-    fn update(&mut self, event_queue: &mut VecDeque<Input>, deadzone: f64) {
-        match (self.joystick.is_present(), self.connected) {
+    fn update(
+        &mut self,
+        event_queue: &mut VecDeque<Event>,
+        deadzone: f64,
+        epsilon: f64,
+        stamp: Option<TimeStamp>,
+    ) {
+        match (self.present, self.connected) {
             // not connected, and we know its not connected
             (false, false) => return,
 
             // was disconnected since last update
             (false, true) => {
                 self.connected = false;
-                // clear maps to free up memory
                 self.buttons.clear();
                 self.axes.clear();
                 return;
             }
 
-            // was connected since last update
-            (true, false) => {
-                // insert values
-                self.connected = true;
+            // connected
+            (true, _) => {}
+        }
 
-                // only issue with this approach is a skipped input on the update the controller is connected
-                // i dont think this is a big issue though
-                for (axis, a) in self.joystick.get_axes().iter().enumerate() {
-                    self.axes.insert(axis as u8, *a as f64);
-                }
-                for (button, a) in self.joystick.get_buttons().iter().enumerate() {
-                    self.buttons.insert(button as u8, *a > 1);
-                }
+        let state = JoystickState::read(&self.joystick, self.gamepad, self.normalize_triggers);
 
-                // exit
-                return;
-            }
+        // was connected since last update
+        if !self.connected {
+            // insert values
+            self.connected = true;
+
+            // only issue with this approach is a skipped input on the update the controller is connected
+            // i dont think this is a big issue though
+            self.axes.clear();
+            self.axes
+                .extend((0..state.axes_len()).map(|axis| state.axis(axis)));
+            self.buttons.clear();
+            self.buttons
+                .extend((0..state.buttons_len()).map(|button| state.button(button)));
 
-            // still connected, nothing to do
-            (true, true) => {}
+            // exit
+            return;
         }
 
+        // the number of axes and buttons can change when gamepad mappings are updated
+        self.axes.resize(state.axes_len(), 0.0);
+        self.buttons.resize(state.buttons_len(), false);
+
         // check axes
-        for (axis, a) in self.joystick.get_axes().iter().enumerate() {
-            let previous = self.axes.get_mut(&(axis as u8)).unwrap();
-            let a = *a as f64;
+        for (axis, previous) in self.axes.iter_mut().enumerate() {
+            let a = state.axis(axis);
+            // values within the deadzone are reported once as the axis at rest
+            let rest = a == 0.0 || a.abs() < deadzone;
+            let a = if rest { 0.0 } else { a };
 
-            if a == *previous || a.abs() < deadzone {
-                // if the value is the same, or within the deadzone, dont do an update
+            if a == *previous || (!rest && *previous != 0.0 && (a - *previous).abs() < epsilon) {
+                // if the value is the same, or barely changed away from rest, dont do an update
                 continue;
             } else {
                 // new value, update existing value
@@ -641,15 +4998,19 @@ impl JoystickHelper {
             }
 
             // add change as event
-            event_queue.push_back(Input::Move(Motion::ControllerAxis(
-                ControllerAxisArgs::new(self.joystick.id as u32, axis as u8, a),
-            )));
+            event_queue.push_back(Event::Input(
+                Input::Move(Motion::ControllerAxis(ControllerAxisArgs::new(
+                    self.joystick.id as u32,
+                    axis as u8,
+                    a,
+                ))),
+                stamp,
+            ));
         }
 
         // check buttons
-        for (button, a) in self.joystick.get_buttons().iter().enumerate() {
-            let previous = self.buttons.get_mut(&(button as u8)).unwrap();
-            let pressed = *a > 0;
+        for (button, previous) in self.buttons.iter_mut().enumerate() {
+            let pressed = state.button(button);
 
             if pressed == *previous {
                 // if the value is the same, dont do an update
@@ -660,18 +5021,440 @@ impl JoystickHelper {
             }
 
             // add change as event
-            event_queue.push_back(Input::Button(ButtonArgs {
-                state: if pressed {
-                    ButtonState::Press
-                } else {
-                    ButtonState::Release
-                },
-                button: Button::Controller(ControllerButton::new(
-                    self.joystick.id as u32,
-                    button as u8,
-                )),
+            event_queue.push_back(Event::Input(
+                Input::Button(ButtonArgs {
+                    state: if pressed {
+                        ButtonState::Press
+                    } else {
+                        ButtonState::Release
+                    },
+                    button: Button::Controller(ControllerButton::new(
+                        self.joystick.id as u32,
+                        button as u8,
+                    )),
+                    scancode: None,
+                }),
+                stamp,
+            ));
+        }
+    }
+}
+
+/// The axes and buttons of a joystick, read without allocating.
+enum JoystickState<'a> {
+    /// Device specific axes and buttons, owned by GLFW.
+    Raw { axes: &'a [f32], buttons: &'a [u8] },
+    /// The standard gamepad layout.
+    Gamepad {
+        state: glfw::GamepadState,
+        normalize_triggers: bool,
+    },
+}
+
+impl<'a> JoystickState<'a> {
+    fn read(joystick: &'a Joystick, gamepad: bool, normalize_triggers: bool) -> JoystickState<'a> {
+        if gamepad {
+            if let Some(state) = joystick.get_gamepad_state() {
+                return JoystickState::Gamepad {
+                    state,
+                    normalize_triggers,
+                };
+            }
+        }
+        // The arrays stay valid until the joystick is read again or disconnected,
+        // which can only happen on this thread while the state is borrowed.
+        unsafe {
+            let id = joystick.id as i32;
+            let mut count = 0;
+            let axes = glfw::ffi::glfwGetJoystickAxes(id, &mut count);
+            let axes: &[f32] = if axes.is_null() {
+                &[]
+            } else {
+                slice::from_raw_parts(axes, count as usize)
+            };
+            let mut count = 0;
+            let buttons = glfw::ffi::glfwGetJoystickButtons(id, &mut count);
+            let buttons: &[u8] = if buttons.is_null() {
+                &[]
+            } else {
+                slice::from_raw_parts(buttons, count as usize)
+            };
+            JoystickState::Raw { axes, buttons }
+        }
+    }
+
+    fn axes_len(&self) -> usize {
+        match *self {
+            JoystickState::Raw { axes, .. } => axes.len(),
+            JoystickState::Gamepad { .. } => glfw::ffi::GAMEPAD_AXIS_LAST as usize + 1,
+        }
+    }
+
+    fn buttons_len(&self) -> usize {
+        match *self {
+            JoystickState::Raw { buttons, .. } => buttons.len(),
+            JoystickState::Gamepad { .. } => glfw::ffi::GAMEPAD_BUTTON_LAST as usize + 1,
+        }
+    }
+
+    /// Returns the position of an axis, or zero if there is no such axis.
+    fn axis(&self, axis: usize) -> f64 {
+        match *self {
+            JoystickState::Raw { axes, .. } => axes.get(axis).map(|&a| a as f64).unwrap_or(0.0),
+            JoystickState::Gamepad {
+                ref state,
+                normalize_triggers,
+            } => match glfw::GamepadAxis::from_i32(axis as i32) {
+                Some(axis @ glfw::GamepadAxis::AxisLeftTrigger)
+                | Some(axis @ glfw::GamepadAxis::AxisRightTrigger)
+                    if normalize_triggers =>
+                {
+                    (state.get_axis(axis) as f64 + 1.0) / 2.0
+                }
+                Some(axis) => state.get_axis(axis) as f64,
+                None => 0.0,
+            },
+        }
+    }
+
+    /// Returns whether a button is pressed.
+    fn button(&self, button: usize) -> bool {
+        match *self {
+            JoystickState::Raw { buttons, .. } => buttons.get(button).is_some_and(|&b| b > 0),
+            JoystickState::Gamepad { ref state, .. } => {
+                match glfw::GamepadButton::from_i32(button as i32) {
+                    Some(button) => state.get_button_state(button) == glfw::Action::Press,
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("glfw_window_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn controller_recording_round_trip() {
+        let recording = ControllerRecording {
+            events: vec![
+                (
+                    Duration::from_millis(0),
+                    Input::Move(Motion::ControllerAxis(ControllerAxisArgs::new(1, 2, 0.25))),
+                ),
+                (
+                    Duration::from_millis(1500),
+                    Input::Button(ButtonArgs {
+                        state: ButtonState::Press,
+                        button: Button::Controller(ControllerButton::new(0, 3)),
+                        scancode: None,
+                    }),
+                ),
+                (
+                    Duration::from_millis(2250),
+                    Input::Button(ButtonArgs {
+                        state: ButtonState::Release,
+                        button: Button::Controller(ControllerButton::new(0, 3)),
+                        scancode: None,
+                    }),
+                ),
+            ],
+        };
+        let path = temp_path("round_trip.txt");
+        recording.save(&path).unwrap();
+        let loaded = ControllerRecording::load(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.unwrap(), recording);
+    }
+
+    #[test]
+    fn controller_recording_rejects_malformed_lines() {
+        let path = temp_path("malformed.txt");
+        for text in &[
+            "0.5 0 axis 1\n",
+            "0.5 0 hat 1 0\n",
+            "x 0 button 1 1\n",
+            "0.5 0 axis 1 up\n",
+            "-1 0 button 1 1\n",
+        ] {
+            fs::write(&path, text).unwrap();
+            let err = ControllerRecording::load(&path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", text);
+        }
+        let _ = fs::remove_file(&path);
+    }
+
+    fn mode(width: u32, height: u32, refresh_rate: u32) -> VideoMode {
+        VideoMode {
+            width,
+            height,
+            refresh_rate,
+        }
+    }
+
+    #[test]
+    fn closest_video_mode_prefers_size_then_refresh_rate() {
+        let modes = [
+            mode(1280, 720, 60),
+            mode(1920, 1080, 60),
+            mode(1920, 1080, 144),
+        ];
+        assert_eq!(
+            closest_video_mode(&modes, mode(1920, 1080, 120)),
+            Some(mode(1920, 1080, 144))
+        );
+        assert_eq!(
+            closest_video_mode(&modes, mode(1366, 768, 144)),
+            Some(mode(1280, 720, 60))
+        );
+        assert_eq!(closest_video_mode(&[], mode(1920, 1080, 60)), None);
+    }
+
+    #[test]
+    fn clamp_to_rect_keeps_positions_inside() {
+        let rect = [10.0, 20.0, 100.0, 50.0];
+        assert_eq!(clamp_to_rect(rect, (50.0, 40.0)), (50.0, 40.0));
+        assert_eq!(clamp_to_rect(rect, (0.0, 0.0)), (10.0, 20.0));
+        assert_eq!(clamp_to_rect(rect, (200.0, 100.0)), (110.0, 70.0));
+    }
+
+    fn resize(w: f64, h: f64) -> ResizeArgs {
+        ResizeArgs {
+            window_size: [w, h],
+            draw_size: [w as u32 * 2, h as u32 * 2],
+        }
+    }
+
+    #[test]
+    fn queue_resize_merges_new_resize_events() {
+        let mut queue = VecDeque::new();
+        queue_resize(&mut queue, 0, resize(100.0, 100.0), Some(1));
+        queue_resize(&mut queue, 0, resize(200.0, 100.0), Some(2));
+        assert_eq!(queue.len(), 1);
+        match queue[0] {
+            Event::Input(Input::Resize(args), stamp) => {
+                assert_eq!(args, resize(200.0, 100.0));
+                assert_eq!(stamp, Some(2));
+            }
+            _ => panic!("expected a resize event"),
+        }
+
+        // Events queued before the current batch are not changed.
+        queue_resize(&mut queue, 1, resize(300.0, 100.0), None);
+        assert_eq!(queue.len(), 2);
+
+        // A resize after another event is queued separately.
+        queue.push_back(Event::Input(Input::Close(CloseArgs), None));
+        queue_resize(&mut queue, 1, resize(400.0, 100.0), None);
+        assert_eq!(queue.len(), 4);
+    }
+
+    #[test]
+    fn stable_controller_ids_forget_slots_on_disconnect() {
+        let mut ids = StableControllerIds {
+            controllers: vec![
+                (Some("a".into()), Some(JoystickId::Joystick2)),
+                (Some("b".into()), None),
+            ],
+        };
+        assert_eq!(ids.get(JoystickId::Joystick2), Some(0));
+        assert_eq!(ids.get(JoystickId::Joystick1), None);
+        assert_eq!(ids.slot(0), Some(JoystickId::Joystick2));
+        assert_eq!(ids.slot(1), None);
+        assert_eq!(ids.slot(2), None);
+
+        ids.disconnect(JoystickId::Joystick2);
+        assert_eq!(ids.get(JoystickId::Joystick2), None);
+        assert_eq!(ids.slot(0), None);
+    }
+
+    #[test]
+    fn coalesce_mouse_moves_merges_runs() {
+        let mut queue = VecDeque::new();
+        queue.push_back(Event::Input(Input::Focus(true), Some(1)));
+        queue.push_back(Event::Input(
+            Input::Move(Motion::MouseCursor([1.0, 1.0])),
+            Some(2),
+        ));
+        queue.push_back(Event::Input(
+            Input::Move(Motion::MouseRelative([1.0, 0.0])),
+            Some(2),
+        ));
+        queue.push_back(Event::Input(
+            Input::Move(Motion::MouseCursor([3.0, 2.0])),
+            Some(3),
+        ));
+        queue.push_back(Event::Input(
+            Input::Move(Motion::MouseRelative([2.0, 1.0])),
+            Some(3),
+        ));
+        queue.push_back(Event::Input(Input::Close(CloseArgs), Some(4)));
+        queue.push_back(Event::Input(
+            Input::Move(Motion::MouseCursor([5.0, 5.0])),
+            Some(5),
+        ));
+        coalesce_mouse_moves(&mut queue, 1);
+
+        let inputs: Vec<(Input, Option<TimeStamp>)> = queue
+            .iter()
+            .map(|event| match *event {
+                Event::Input(ref input, stamp) => (input.clone(), stamp),
+                _ => panic!("expected an input event"),
+            })
+            .collect();
+        assert_eq!(
+            inputs,
+            vec![
+                (Input::Focus(true), Some(1)),
+                (Input::Move(Motion::MouseCursor([3.0, 2.0])), Some(3)),
+                (Input::Move(Motion::MouseRelative([3.0, 1.0])), Some(3)),
+                (Input::Close(CloseArgs), Some(4)),
+                (Input::Move(Motion::MouseCursor([5.0, 5.0])), Some(5)),
+            ]
+        );
+    }
+
+    fn keyboard_inputs(queue: &VecDeque<Event>) -> Vec<(ButtonState, keyboard::Key)> {
+        queue
+            .iter()
+            .filter_map(|event| match *event {
+                Event::Input(
+                    Input::Button(ButtonArgs {
+                        state,
+                        button: Button::Keyboard(key),
+                        ..
+                    }),
+                    _,
+                ) => Some((state, key)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sticky_modifiers_latch_a_tapped_modifier() {
+        let mut sticky = StickyModifiers::new();
+        let mut queue = VecDeque::new();
+        sticky.press(glfw::Key::LeftShift, &mut queue);
+        sticky.release(glfw::Key::LeftShift, 50, keyboard::Key::LShift, &mut queue);
+        assert!(queue.is_empty());
+
+        sticky.press(glfw::Key::A, &mut queue);
+        assert_eq!(
+            keyboard_inputs(&queue),
+            vec![(ButtonState::Press, keyboard::Key::LShift)]
+        );
+        queue.clear();
+
+        sticky.release(glfw::Key::A, 38, keyboard::Key::A, &mut queue);
+        assert_eq!(
+            keyboard_inputs(&queue),
+            vec![(ButtonState::Release, keyboard::Key::LShift)]
+        );
+        queue.clear();
+
+        // The modifier only applies to one key press.
+        sticky.press(glfw::Key::B, &mut queue);
+        sticky.release(glfw::Key::B, 56, keyboard::Key::B, &mut queue);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn sticky_modifiers_tapped_twice_are_released() {
+        let mut sticky = StickyModifiers::new();
+        let mut queue = VecDeque::new();
+        for _ in 0..2 {
+            sticky.press(glfw::Key::LeftControl, &mut queue);
+            sticky.release(glfw::Key::LeftControl, 37, keyboard::Key::LCtrl, &mut queue);
+        }
+        sticky.press(glfw::Key::A, &mut queue);
+        sticky.release(glfw::Key::A, 38, keyboard::Key::A, &mut queue);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn config_watcher_reads_changed_values() {
+        let path = temp_path("config.txt");
+        fs::write(
+            &path,
+            "# window\ntitle = Game # comment\nwidth=800\nnot a setting\n",
+        )
+        .unwrap();
+        let mut watcher = ConfigWatcher::new(path.clone());
+        let changes = watcher.changes();
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            changes,
+            vec![
+                ("title".to_string(), "Game".to_string()),
+                ("width".to_string(), "800".to_string()),
+            ]
+        );
+        // The file is not checked again right away.
+        assert!(watcher.changes().is_empty());
+    }
+
+    #[test]
+    fn controller_remap_renames_and_inverts() {
+        let mut remap = ControllerRemap::default();
+        remap.axes.insert(0, 1);
+        remap.inverted_axes.push(1);
+        remap.buttons.insert(2, 5);
+
+        let mut axis = Event::Input(
+            Input::Move(Motion::ControllerAxis(ControllerAxisArgs::new(0, 0, 0.5))),
+            None,
+        );
+        remap.apply(&mut axis);
+        match axis {
+            Event::Input(Input::Move(Motion::ControllerAxis(args)), _) => {
+                assert_eq!((args.axis, args.position), (1, -0.5));
+            }
+            _ => panic!("expected a controller axis event"),
+        }
+
+        let mut button = Event::Input(
+            Input::Button(ButtonArgs {
+                state: ButtonState::Press,
+                button: Button::Controller(ControllerButton::new(0, 2)),
                 scancode: None,
-            }));
+            }),
+            None,
+        );
+        remap.apply(&mut button);
+        match button {
+            Event::Input(
+                Input::Button(ButtonArgs {
+                    button: Button::Controller(button),
+                    ..
+                }),
+                _,
+            ) => assert_eq!(button.button, 5),
+            _ => panic!("expected a controller button event"),
         }
     }
+
+    #[test]
+    fn flip_rows_reverses_rows() {
+        let pixels: Vec<u8> = (0..24).collect();
+        let flipped = flip_rows(&pixels, 2);
+        assert_eq!(&flipped[..8], &pixels[16..]);
+        assert_eq!(&flipped[8..16], &pixels[8..16]);
+        assert_eq!(&flipped[16..], &pixels[..8]);
+        assert!(flip_rows(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn mouse_pos_is_forgotten_while_motion_is_paused() {
+        let last = Some((10.0, 10.0));
+        assert_eq!(tracked_mouse_pos(last, true), last);
+        assert_eq!(tracked_mouse_pos(last, false), None);
+        // When motion is reported again, it is measured from the next position.
+        assert_eq!(tracked_mouse_pos(None, true), None);
+    }
 }